@@ -4,12 +4,13 @@
 // Copyright: 2018, Valerian Saliou <valerian@valeriansaliou.name>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use ipnet::IpNet;
 use std::collections::BTreeMap;
 use std::net::SocketAddr;
 use url_serde::SerdeUrl;
 
 use super::defaults;
-use crate::dns::record::RecordName;
+use crate::dns::record::{RecordName, RecordNamePattern};
 use crate::dns::zone::ZoneName;
 
 #[derive(Deserialize)]
@@ -61,14 +62,63 @@ pub struct ConfigDNS {
     #[serde(default = "defaults::dns_zone")]
     pub zone: BTreeMap<String, ConfigDNSZone>,
 
+    #[serde(default = "defaults::dns_zone_sync_interval")]
+    pub zone_sync_interval: u64,
+
     pub flatten: ConfigDNSFlatten,
 
     #[serde(default = "defaults::dns_health")]
     pub health: ConfigDNSHealth,
+
+    #[serde(default = "defaults::dns_update")]
+    pub update: ConfigDNSUpdate,
+
+    #[serde(default = "defaults::dns_catalog")]
+    pub catalog: ConfigDNSCatalog,
+
+    #[serde(default = "defaults::dns_tls")]
+    pub tls: ConfigDNSTLS,
+
+    #[serde(default = "defaults::dns_https")]
+    pub https: ConfigDNSHTTPS,
+
+    #[serde(default = "defaults::dns_quic")]
+    pub quic: ConfigDNSQUIC,
+
+    #[serde(default = "defaults::dns_proxy_protocol")]
+    pub proxy_protocol: ConfigDNSProxyProtocol,
+
+    #[serde(default = "defaults::dns_rate_limit")]
+    pub rate_limit: ConfigDNSRateLimit,
 }
 
 #[derive(Deserialize)]
-pub struct ConfigDNSZone {}
+pub struct ConfigDNSZone {
+    #[serde(default = "defaults::dns_zone_mode")]
+    pub mode: ConfigDNSZoneMode,
+
+    pub primary: Option<SocketAddr>,
+
+    pub nameservers: Option<Vec<String>>,
+
+    pub soa_master: Option<String>,
+    pub soa_responsible: Option<String>,
+    pub soa_refresh: Option<i32>,
+    pub soa_retry: Option<i32>,
+    pub soa_expire: Option<i32>,
+    pub soa_ttl: Option<u32>,
+
+    pub record_ttl: Option<u32>,
+}
+
+#[derive(Deserialize, PartialEq)]
+pub enum ConfigDNSZoneMode {
+    #[serde(rename = "primary")]
+    Primary,
+
+    #[serde(rename = "secondary")]
+    Secondary,
+}
 
 #[derive(Default, Deserialize)]
 pub struct ConfigDNSFlatten {
@@ -135,6 +185,117 @@ pub enum ConfigDNSHealthHTTPMethod {
     Get,
 }
 
+#[derive(Deserialize)]
+pub struct ConfigDNSCatalog {
+    #[serde(default = "defaults::dns_catalog_enable")]
+    pub enable: bool,
+
+    #[serde(default = "defaults::dns_catalog_zone")]
+    pub zone: String,
+
+    #[serde(default = "defaults::dns_catalog_transfer_allow")]
+    pub transfer_allow: Vec<IpNet>,
+}
+
+#[derive(Deserialize)]
+pub struct ConfigDNSTLS {
+    #[serde(default = "defaults::dns_tls_inets")]
+    pub inets: Vec<SocketAddr>,
+
+    pub certificate_path: Option<String>,
+    pub key_path: Option<String>,
+
+    #[serde(default = "defaults::dns_tls_reload_interval")]
+    pub reload_interval: u64,
+}
+
+#[derive(Deserialize)]
+pub struct ConfigDNSHTTPS {
+    #[serde(default = "defaults::dns_https_inets")]
+    pub inets: Vec<SocketAddr>,
+
+    #[serde(default = "defaults::dns_https_tls")]
+    pub tls: bool,
+
+    #[serde(default = "defaults::dns_https_path")]
+    pub path: String,
+
+    #[serde(default = "defaults::dns_https_trusted_proxies")]
+    pub trusted_proxies: Vec<IpNet>,
+}
+
+#[derive(Deserialize)]
+pub struct ConfigDNSQUIC {
+    #[serde(default = "defaults::dns_quic_inets")]
+    pub inets: Vec<SocketAddr>,
+}
+
+#[derive(Deserialize)]
+pub struct ConfigDNSRateLimit {
+    #[serde(default = "defaults::dns_rate_limit_enable")]
+    pub enable: bool,
+
+    #[serde(default = "defaults::dns_rate_limit_responses_per_second")]
+    pub responses_per_second: u32,
+
+    #[serde(default = "defaults::dns_rate_limit_nxdomains_per_second")]
+    pub nxdomains_per_second: u32,
+
+    #[serde(default = "defaults::dns_rate_limit_errors_per_second")]
+    pub errors_per_second: u32,
+
+    #[serde(default = "defaults::dns_rate_limit_window")]
+    pub window: u32,
+
+    #[serde(default = "defaults::dns_rate_limit_slip")]
+    pub slip: u32,
+
+    #[serde(default = "defaults::dns_rate_limit_ipv4_prefix_length")]
+    pub ipv4_prefix_length: u8,
+
+    #[serde(default = "defaults::dns_rate_limit_ipv6_prefix_length")]
+    pub ipv6_prefix_length: u8,
+
+    #[serde(default = "defaults::dns_rate_limit_exempt_clients")]
+    pub exempt_clients: Vec<IpNet>,
+}
+
+#[derive(Deserialize)]
+pub struct ConfigDNSProxyProtocol {
+    #[serde(default = "defaults::dns_proxy_protocol_trusted_sources")]
+    pub trusted_sources: Vec<IpNet>,
+}
+
+#[derive(Default, Deserialize)]
+pub struct ConfigDNSUpdate {
+    #[serde(default = "defaults::dns_update_key")]
+    pub key: Vec<ConfigDNSUpdateKey>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct ConfigDNSUpdateKey {
+    pub name: String,
+
+    #[serde(default = "defaults::dns_update_key_algorithm")]
+    pub algorithm: ConfigDNSUpdateKeyAlgorithm,
+
+    pub secret: String,
+    pub zones: Vec<ZoneName>,
+    pub names: Option<Vec<RecordNamePattern>>,
+}
+
+#[derive(Clone, Deserialize, PartialEq)]
+pub enum ConfigDNSUpdateKeyAlgorithm {
+    #[serde(rename = "hmac-sha256")]
+    HmacSha256,
+
+    #[serde(rename = "hmac-sha384")]
+    HmacSha384,
+
+    #[serde(rename = "hmac-sha512")]
+    HmacSha512,
+}
+
 #[derive(Deserialize)]
 pub struct ConfigGeo {
     #[serde(default = "defaults::geo_database_path")]
@@ -160,7 +321,31 @@ pub struct ConfigHTTP {
     #[serde(default = "defaults::http_workers")]
     pub workers: usize,
 
-    pub record_token: String,
+    pub record_token: Option<String>,
+
+    #[serde(default = "defaults::http_token")]
+    pub token: Vec<ConfigHTTPToken>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct ConfigHTTPToken {
+    pub name: String,
+    pub secret: String,
+    pub zones: Option<Vec<ZoneName>>,
+    pub operations: Vec<ConfigHTTPTokenOperation>,
+    pub names: Option<Vec<RecordNamePattern>>,
+}
+
+#[derive(Clone, Deserialize, PartialEq)]
+pub enum ConfigHTTPTokenOperation {
+    #[serde(rename = "read")]
+    Read,
+
+    #[serde(rename = "write")]
+    Write,
+
+    #[serde(rename = "metrics")]
+    Metrics,
 }
 
 #[derive(Deserialize)]
@@ -189,6 +374,9 @@ pub struct ConfigRedis {
     #[serde(default = "defaults::redis_cache_expire_seconds")]
     pub cache_expire_seconds: u64,
 
+    #[serde(default = "defaults::redis_history_size")]
+    pub history_size: usize,
+
     #[serde(default = "defaults::redis_master")]
     pub master: ConfigRedisMaster,
 
@@ -220,4 +408,82 @@ impl ConfigDNS {
     pub fn zone_exists(&self, name: &str) -> bool {
         self.zone.contains_key(name)
     }
+
+    pub fn zone_nameservers(&self, name: &str) -> &Vec<String> {
+        self.zone
+            .get(name)
+            .and_then(|zone| zone.nameservers.as_ref())
+            .unwrap_or(&self.nameservers)
+    }
+
+    pub fn zone_soa_master(&self, name: &str) -> &str {
+        self.zone
+            .get(name)
+            .and_then(|zone| zone.soa_master.as_deref())
+            .unwrap_or(&self.soa_master)
+    }
+
+    pub fn zone_soa_responsible(&self, name: &str) -> &str {
+        self.zone
+            .get(name)
+            .and_then(|zone| zone.soa_responsible.as_deref())
+            .unwrap_or(&self.soa_responsible)
+    }
+
+    pub fn zone_soa_refresh(&self, name: &str) -> i32 {
+        self.zone
+            .get(name)
+            .and_then(|zone| zone.soa_refresh)
+            .unwrap_or(self.soa_refresh)
+    }
+
+    pub fn zone_soa_retry(&self, name: &str) -> i32 {
+        self.zone
+            .get(name)
+            .and_then(|zone| zone.soa_retry)
+            .unwrap_or(self.soa_retry)
+    }
+
+    pub fn zone_soa_expire(&self, name: &str) -> i32 {
+        self.zone
+            .get(name)
+            .and_then(|zone| zone.soa_expire)
+            .unwrap_or(self.soa_expire)
+    }
+
+    pub fn zone_soa_ttl(&self, name: &str) -> u32 {
+        self.zone
+            .get(name)
+            .and_then(|zone| zone.soa_ttl)
+            .unwrap_or(self.soa_ttl)
+    }
+
+    pub fn zone_record_ttl(&self, name: &str) -> u32 {
+        self.zone
+            .get(name)
+            .and_then(|zone| zone.record_ttl)
+            .unwrap_or(self.record_ttl)
+    }
+}
+
+impl ConfigHTTPToken {
+    pub fn allows(&self, operation: &ConfigHTTPTokenOperation, zone_name: &ZoneName) -> bool {
+        self.operations.contains(operation)
+            && self
+                .zones
+                .as_ref()
+                .map(|zones| zones.contains(zone_name))
+                .unwrap_or(true)
+    }
+
+    pub fn allows_name(&self, record_name: &RecordName) -> bool {
+        self.names
+            .as_ref()
+            .map(|names| names.iter().any(|pattern| pattern.matches(record_name)))
+            .unwrap_or(true)
+    }
+
+    pub fn allows_all_names(&self) -> bool {
+        self.names.is_none()
+    }
 }
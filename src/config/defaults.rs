@@ -4,12 +4,16 @@
 // Copyright: 2018, Valerian Saliou <valerian@valeriansaliou.name>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use ipnet::IpNet;
 use std::collections::BTreeMap;
 use std::net::SocketAddr;
 
 use super::config::{
-    ConfigDNSHealth, ConfigDNSHealthHTTP, ConfigDNSHealthHTTPMethod, ConfigDNSHealthNotify,
-    ConfigDNSZone, ConfigRedisMaster,
+    ConfigDNSCatalog, ConfigDNSHTTPS, ConfigDNSHealth, ConfigDNSHealthHTTP,
+    ConfigDNSHealthHTTPMethod, ConfigDNSHealthNotify, ConfigDNSProxyProtocol, ConfigDNSQUIC,
+    ConfigDNSRateLimit, ConfigDNSTLS, ConfigDNSUpdate, ConfigDNSUpdateKey,
+    ConfigDNSUpdateKeyAlgorithm, ConfigDNSZone, ConfigDNSZoneMode, ConfigHTTPToken,
+    ConfigRedisMaster,
 };
 
 pub fn server_log_level() -> String {
@@ -52,6 +56,14 @@ pub fn dns_zone() -> BTreeMap<String, ConfigDNSZone> {
     BTreeMap::new()
 }
 
+pub fn dns_zone_sync_interval() -> u64 {
+    10
+}
+
+pub fn dns_zone_mode() -> ConfigDNSZoneMode {
+    ConfigDNSZoneMode::Primary
+}
+
 pub fn dns_health() -> ConfigDNSHealth {
     ConfigDNSHealth {
         check_enable: dns_health_check_enable(),
@@ -107,6 +119,152 @@ pub fn dns_health_http_expected_status() -> Vec<u16> {
     vec![200]
 }
 
+pub fn dns_update() -> ConfigDNSUpdate {
+    ConfigDNSUpdate {
+        key: dns_update_key(),
+    }
+}
+
+pub fn dns_update_key() -> Vec<ConfigDNSUpdateKey> {
+    Vec::new()
+}
+
+pub fn dns_update_key_algorithm() -> ConfigDNSUpdateKeyAlgorithm {
+    ConfigDNSUpdateKeyAlgorithm::HmacSha256
+}
+
+pub fn dns_catalog() -> ConfigDNSCatalog {
+    ConfigDNSCatalog {
+        enable: dns_catalog_enable(),
+        zone: dns_catalog_zone(),
+        transfer_allow: dns_catalog_transfer_allow(),
+    }
+}
+
+pub fn dns_catalog_enable() -> bool {
+    false
+}
+
+pub fn dns_catalog_zone() -> String {
+    "catalog.invalid".to_string()
+}
+
+pub fn dns_catalog_transfer_allow() -> Vec<IpNet> {
+    Vec::new()
+}
+
+pub fn dns_tls() -> ConfigDNSTLS {
+    ConfigDNSTLS {
+        inets: dns_tls_inets(),
+        certificate_path: None,
+        key_path: None,
+        reload_interval: dns_tls_reload_interval(),
+    }
+}
+
+pub fn dns_tls_inets() -> Vec<SocketAddr> {
+    Vec::new()
+}
+
+pub fn dns_tls_reload_interval() -> u64 {
+    300
+}
+
+pub fn dns_https() -> ConfigDNSHTTPS {
+    ConfigDNSHTTPS {
+        inets: dns_https_inets(),
+        tls: dns_https_tls(),
+        path: dns_https_path(),
+        trusted_proxies: dns_https_trusted_proxies(),
+    }
+}
+
+pub fn dns_https_inets() -> Vec<SocketAddr> {
+    Vec::new()
+}
+
+pub fn dns_https_tls() -> bool {
+    true
+}
+
+pub fn dns_https_path() -> String {
+    "/dns-query".to_string()
+}
+
+pub fn dns_https_trusted_proxies() -> Vec<IpNet> {
+    vec!["127.0.0.1/32".parse().unwrap(), "::1/128".parse().unwrap()]
+}
+
+pub fn dns_quic() -> ConfigDNSQUIC {
+    ConfigDNSQUIC {
+        inets: dns_quic_inets(),
+    }
+}
+
+pub fn dns_quic_inets() -> Vec<SocketAddr> {
+    Vec::new()
+}
+
+pub fn dns_rate_limit() -> ConfigDNSRateLimit {
+    ConfigDNSRateLimit {
+        enable: dns_rate_limit_enable(),
+        responses_per_second: dns_rate_limit_responses_per_second(),
+        nxdomains_per_second: dns_rate_limit_nxdomains_per_second(),
+        errors_per_second: dns_rate_limit_errors_per_second(),
+        window: dns_rate_limit_window(),
+        slip: dns_rate_limit_slip(),
+        ipv4_prefix_length: dns_rate_limit_ipv4_prefix_length(),
+        ipv6_prefix_length: dns_rate_limit_ipv6_prefix_length(),
+        exempt_clients: dns_rate_limit_exempt_clients(),
+    }
+}
+
+pub fn dns_rate_limit_enable() -> bool {
+    false
+}
+
+pub fn dns_rate_limit_responses_per_second() -> u32 {
+    10
+}
+
+pub fn dns_rate_limit_nxdomains_per_second() -> u32 {
+    5
+}
+
+pub fn dns_rate_limit_errors_per_second() -> u32 {
+    5
+}
+
+pub fn dns_rate_limit_window() -> u32 {
+    15
+}
+
+pub fn dns_rate_limit_slip() -> u32 {
+    2
+}
+
+pub fn dns_rate_limit_ipv4_prefix_length() -> u8 {
+    24
+}
+
+pub fn dns_rate_limit_ipv6_prefix_length() -> u8 {
+    56
+}
+
+pub fn dns_rate_limit_exempt_clients() -> Vec<IpNet> {
+    Vec::new()
+}
+
+pub fn dns_proxy_protocol() -> ConfigDNSProxyProtocol {
+    ConfigDNSProxyProtocol {
+        trusted_sources: dns_proxy_protocol_trusted_sources(),
+    }
+}
+
+pub fn dns_proxy_protocol_trusted_sources() -> Vec<IpNet> {
+    Vec::new()
+}
+
 pub fn geo_database_path() -> String {
     "./res/geo/".to_string()
 }
@@ -131,6 +289,10 @@ pub fn http_workers() -> usize {
     2
 }
 
+pub fn http_token() -> Vec<ConfigHTTPToken> {
+    Vec::new()
+}
+
 pub fn redis_database() -> u8 {
     0
 }
@@ -163,6 +325,10 @@ pub fn redis_cache_expire_seconds() -> u64 {
     600
 }
 
+pub fn redis_history_size() -> usize {
+    20
+}
+
 pub fn redis_master() -> ConfigRedisMaster {
     ConfigRedisMaster {
         host: redis_master_host(),
@@ -9,3 +9,4 @@ mod defaults;
 pub mod config;
 pub mod logger;
 pub mod reader;
+pub mod reload;
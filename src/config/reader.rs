@@ -4,8 +4,11 @@
 // Copyright: 2018, Valerian Saliou <valerian@valeriansaliou.name>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use hickory_proto::rr::Name;
+use log::LevelFilter;
 use std::fs::File;
 use std::io::Read;
+use std::str::FromStr;
 use toml;
 
 use super::config::*;
@@ -15,16 +18,84 @@ pub struct ConfigReader;
 
 impl ConfigReader {
     pub fn make() -> Config {
+        Self::read().unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn read() -> Result<Config, String> {
         debug!("reading config file: {}", &APP_ARGS.config);
 
-        let mut file = File::open(&APP_ARGS.config).expect("cannot find config file");
+        let mut file = File::open(&APP_ARGS.config)
+            .map_err(|err| format!("cannot find config file: {}", err))?;
         let mut conf = String::new();
 
         file.read_to_string(&mut conf)
-            .expect("cannot read config file");
+            .map_err(|err| format!("cannot read config file: {}", err))?;
 
         debug!("read config file: {}", &APP_ARGS.config);
 
-        toml::from_str(&conf).expect("syntax error in config file")
+        let conf: Config =
+            toml::from_str(&conf).map_err(|err| format!("syntax error in config file: {}", err))?;
+
+        // Notice: configuration is validated as a whole before use, so that a configuration \
+        //   reload never gets partially applied.
+        Self::validate(&conf)?;
+
+        Ok(conf)
+    }
+
+    fn validate(conf: &Config) -> Result<(), String> {
+        LevelFilter::from_str(&conf.server.log_level)
+            .map_err(|_| format!("invalid log level: {}", conf.server.log_level))?;
+
+        // Ensure that there is at least a DNS flatten resolver configured
+        if conf.dns.flatten.resolvers.is_empty() {
+            return Err(format!(
+                "dns flatten resolver list is empty, please provide at least a resolver in [{}]",
+                "dns.flatten.resolvers"
+            ));
+        }
+
+        // Ensure that the DNS TLS reload interval is not zero (as it cannot tick otherwise)
+        if conf.dns.tls.reload_interval == 0 {
+            return Err(format!(
+                "dns tls reload interval must be greater than zero, please fix it in [{}]",
+                "dns.tls.reload_interval"
+            ));
+        }
+
+        // Ensure that all SOA and NS names are valid domain names (as they are parsed at runtime)
+        Self::validate_name(&conf.dns.soa_master)?;
+        Self::validate_name(&conf.dns.soa_responsible)?;
+
+        for nameserver in &conf.dns.nameservers {
+            Self::validate_name(nameserver)?;
+        }
+
+        for (zone_name, zone) in &conf.dns.zone {
+            // Ensure that all secondary zones have a primary configured
+            if zone.mode == ConfigDNSZoneMode::Secondary && zone.primary.is_none() {
+                return Err(format!(
+                    "dns zone {} is secondary but has no primary, please provide one in [{}]",
+                    zone_name, "dns.zone.primary"
+                ));
+            }
+
+            // Ensure that all zone names are valid domain names (as they are parsed at runtime)
+            Self::validate_name(zone_name)?;
+            Self::validate_name(conf.dns.zone_soa_master(zone_name))?;
+            Self::validate_name(conf.dns.zone_soa_responsible(zone_name))?;
+
+            for nameserver in conf.dns.zone_nameservers(zone_name) {
+                Self::validate_name(nameserver)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_name(name: &str) -> Result<(), String> {
+        Name::parse(name, Some(&Name::root()))
+            .map(|_| ())
+            .map_err(|err| format!("invalid domain name in config file: {}: {}", name, err))
     }
 }
@@ -0,0 +1,82 @@
+// Constellation
+//
+// Pluggable authoritative DNS server
+// Copyright: 2026, Valerian Saliou <valerian@valeriansaliou.name>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use log::LevelFilter;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::task;
+
+use super::reader::ConfigReader;
+use crate::dns::catalog::{DNSCatalog, DNS_CATALOG};
+use crate::dns::flatten::{DNSFlattenBuilder, DNS_FLATTEN};
+use crate::dns::health::DNSHealth;
+use crate::dns::registry::DNS_REGISTRY;
+use crate::dns::secondary::DNS_SECONDARY;
+use crate::APP_CONF;
+
+pub struct ConfigReloadBuilder;
+pub struct ConfigReload;
+
+impl ConfigReloadBuilder {
+    pub fn new() -> ConfigReload {
+        ConfigReload {}
+    }
+}
+
+impl ConfigReload {
+    #[tokio::main]
+    pub async fn run(&self) {
+        let mut hangups = signal(SignalKind::hangup()).expect("cannot listen for sighup signal");
+
+        info!("configuration reload is now active (send sighup to reload)");
+
+        while hangups.recv().await.is_some() {
+            info!("received sighup, reloading configuration...");
+
+            // Notice: reload is run as a blocking task, as building the flatten resolver spawns \
+            //   its own runtime (which cannot be done, nor dropped from an async context).
+            match task::spawn_blocking(Self::reload).await {
+                Ok(Ok(_)) => info!("reloaded configuration"),
+                Ok(Err(err)) => error!("failed reloading configuration, kept current: {}", err),
+                Err(err) => error!("failed reloading configuration, kept current: {}", err),
+            }
+        }
+    }
+
+    fn reload() -> Result<(), String> {
+        // Read and validate new configuration
+        let conf = ConfigReader::read()?;
+
+        let log_level = LevelFilter::from_str(&conf.server.log_level)
+            .map_err(|_| format!("invalid log level: {}", conf.server.log_level))?;
+
+        // Build new flatten resolver beforehand, as its name servers might not resolve
+        let resolver = DNSFlattenBuilder::build_resolver(&conf.dns.flatten.resolvers)?;
+
+        // Parse new catalog zone beforehand, as it might be invalid
+        let catalog_origin = DNSCatalog::build_origin(&conf.dns.catalog)?;
+
+        // Swap configuration (from there, the new configuration is used everywhere)
+        APP_CONF.store(Arc::new(conf));
+
+        // Apply new configuration to states that were derived from the previous configuration
+        log::set_max_level(log_level);
+
+        DNS_FLATTEN.swap_resolver(resolver);
+
+        // Notice: the catalog origin must be swapped before reloading the registry, as the \
+        //   registry refreshes the catalog zone.
+        DNS_CATALOG.swap_origin(catalog_origin);
+
+        DNS_REGISTRY.reload();
+        DNS_SECONDARY.reload();
+
+        DNSHealth::reload();
+
+        Ok(())
+    }
+}
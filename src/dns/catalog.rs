@@ -0,0 +1,340 @@
+// Constellation
+//
+// Pluggable authoritative DNS server
+// Copyright: 2026, Valerian Saliou <valerian@valeriansaliou.name>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use hickory_proto::op::{Header, LowerQuery, Message, ResponseCode};
+use hickory_proto::rr::dnssec::DigestType;
+use hickory_proto::rr::rdata as HickoryRData;
+use hickory_proto::rr::record_data::RData;
+use hickory_proto::rr::{
+    LowerName, Name, Record, RecordSet, RecordType as HickoryRecordType, RrKey,
+};
+use hickory_proto::serialize::binary::BinEncodable;
+use hickory_server::authority::ZoneType;
+use hickory_server::server::{Protocol, Request};
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::sync::{Arc, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::handler::DNSAuthority;
+use super::registry::DNS_REGISTRY;
+use crate::config::config::ConfigDNSCatalog;
+use crate::APP_CONF;
+
+lazy_static! {
+    pub static ref DNS_CATALOG: DNSCatalog = DNSCatalogBuilder::new();
+}
+
+struct DNSCatalogBuilder;
+
+pub struct DNSCatalog {
+    origin: RwLock<Option<Name>>,
+    zone: RwLock<Option<DNSCatalogZone>>,
+}
+
+struct DNSCatalogZone {
+    members: Vec<String>,
+    serial: u32,
+    records: Vec<Record>,
+    authority: Arc<DNSAuthority>,
+}
+
+const CATALOG_VERSION: &str = "2";
+const CATALOG_TTL: u32 = 0;
+const TRANSFER_RECORDS_PER_MESSAGE: usize = 100;
+
+impl DNSCatalogBuilder {
+    fn new() -> DNSCatalog {
+        let origin =
+            DNSCatalog::build_origin(&APP_CONF.load().dns.catalog).expect("invalid catalog zone");
+
+        let catalog = DNSCatalog {
+            origin: RwLock::new(origin),
+            zone: RwLock::new(None),
+        };
+
+        catalog.refresh();
+
+        catalog
+    }
+}
+
+impl DNSCatalog {
+    pub fn build_origin(catalog: &ConfigDNSCatalog) -> Result<Option<Name>, String> {
+        if catalog.enable {
+            Name::parse(&catalog.zone, Some(&Name::root()))
+                .map(Some)
+                .map_err(|err| format!("invalid catalog zone: {}: {}", catalog.zone, err))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn swap_origin(&self, origin: Option<Name>) {
+        let mut current = self.origin.write().unwrap();
+
+        // Catalog origin did not change? (nothing to swap)
+        if *current == origin {
+            return;
+        }
+
+        match origin {
+            Some(ref origin) => info!("swapped catalog zone to: {}", origin),
+            None => info!("disabled catalog zone"),
+        }
+
+        *current = origin;
+
+        // Notice: the catalog zone is built again upon next refresh, with the new origin.
+        *self.zone.write().unwrap() = None;
+    }
+
+    pub fn refresh(&self) {
+        let origin = match *self.origin.read().unwrap() {
+            Some(ref origin) => origin.to_owned(),
+            None => return,
+        };
+
+        // List member zones (the catalog lists all zones that we are authoritative for)
+        let members: Vec<String> = DNS_REGISTRY.zone_names();
+
+        let mut zone = self.zone.write().unwrap();
+
+        // Catalog did not change? (keep current serial, so that secondaries do not transfer it)
+        if zone.as_ref().map(|zone| &zone.members) == Some(&members) {
+            return;
+        }
+
+        // Bump serial (serial always increases, even across restarts)
+        let serial = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs() as u32)
+            .unwrap_or(1)
+            .max(zone.as_ref().map(|zone| zone.serial + 1).unwrap_or(1));
+
+        match Self::build(&origin, &members, serial) {
+            Ok((records, authority)) => {
+                info!(
+                    "built catalog zone: {} with {} members at serial: {}",
+                    origin,
+                    members.len(),
+                    serial
+                );
+
+                *zone = Some(DNSCatalogZone {
+                    members,
+                    serial,
+                    records,
+                    authority: Arc::new(authority),
+                });
+            }
+            Err(err) => error!("could not build catalog zone: {}: {}", origin, err),
+        }
+    }
+
+    pub fn find_authority(&self, name: &LowerName) -> Option<(LowerName, Arc<DNSAuthority>)> {
+        let origin = LowerName::new(self.origin.read().unwrap().as_ref()?);
+
+        if origin.zone_of(name) {
+            self.zone
+                .read()
+                .unwrap()
+                .as_ref()
+                .map(|zone| (origin, zone.authority.clone()))
+        } else {
+            None
+        }
+    }
+
+    pub fn is_allowed(&self, ip: &IpAddr) -> bool {
+        APP_CONF
+            .load()
+            .dns
+            .catalog
+            .transfer_allow
+            .iter()
+            .any(|network| network.contains(ip))
+    }
+
+    pub fn is_transfer(&self, query: &LowerQuery) -> bool {
+        match (query.query_type(), self.origin.read().unwrap().as_ref()) {
+            (HickoryRecordType::AXFR | HickoryRecordType::IXFR, Some(origin)) => {
+                query.name() == &LowerName::new(origin)
+            }
+            _ => false,
+        }
+    }
+
+    pub fn handle_transfer(&self, request: &Request) -> Vec<Vec<u8>> {
+        info!(
+            "transfer request with identifier: {} for catalog zone from: {}",
+            request.id(),
+            request.src().ip()
+        );
+
+        // Transfers are only allowed over streams (ie. not over UDP or HTTPS), and to allowed \
+        //   clients
+        if !matches!(
+            request.protocol(),
+            Protocol::Tcp | Protocol::Tls | Protocol::Quic
+        ) || !self.is_allowed(&request.src().ip())
+        {
+            warn!(
+                "refused catalog zone transfer to: {} over: {}",
+                request.src().ip(),
+                request.protocol()
+            );
+
+            return Self::transfer_message(request, ResponseCode::Refused, &[])
+                .into_iter()
+                .collect();
+        }
+
+        let zone = self.zone.read().unwrap();
+
+        let records = match zone.as_ref() {
+            Some(zone) => &zone.records,
+            None => {
+                return Self::transfer_message(request, ResponseCode::ServFail, &[])
+                    .into_iter()
+                    .collect()
+            }
+        };
+
+        // Send zone records, closed with the opening SOA record (IXFR requests get a full \
+        //   transfer, which is allowed as per RFC 1995)
+        // Notice: the SOA record is always the first record of the catalog zone.
+        let transfer_records: Vec<&Record> = records.iter().chain(records.first()).collect();
+
+        let mut messages = Vec::new();
+
+        for chunk in transfer_records.chunks(TRANSFER_RECORDS_PER_MESSAGE) {
+            match Self::transfer_message(request, ResponseCode::NoError, chunk) {
+                Some(message) => messages.push(message),
+                None => return Vec::new(),
+            }
+        }
+
+        messages
+    }
+
+    fn build(
+        origin: &Name,
+        members: &[String],
+        serial: u32,
+    ) -> Result<(Vec<Record>, DNSAuthority), String> {
+        let mut records = Vec::new();
+
+        // Insert base SOA and NS records (as per RFC 9432, the NS record points to 'invalid.')
+        records.push(Record::from_rdata(
+            origin.to_owned(),
+            CATALOG_TTL,
+            RData::SOA(HickoryRData::SOA::new(
+                Name::parse("invalid.", None).or(Err("invalid soa master"))?,
+                Name::parse("invalid.", None).or(Err("invalid soa responsible"))?,
+                serial,
+                APP_CONF.load().dns.soa_refresh,
+                APP_CONF.load().dns.soa_retry,
+                APP_CONF.load().dns.soa_expire,
+                CATALOG_TTL,
+            )),
+        ));
+
+        records.push(Record::from_rdata(
+            origin.to_owned(),
+            CATALOG_TTL,
+            RData::NS(HickoryRData::NS(
+                Name::parse("invalid.", None).or(Err("invalid ns"))?,
+            )),
+        ));
+
+        // Insert catalog schema version
+        records.push(Record::from_rdata(
+            Name::parse("version", Some(origin)).map_err(|err| err.to_string())?,
+            CATALOG_TTL,
+            RData::TXT(HickoryRData::TXT::new(vec![CATALOG_VERSION.to_string()])),
+        ));
+
+        // Insert member zones
+        let zones_name = Name::parse("zones", Some(origin)).map_err(|err| err.to_string())?;
+
+        for member in members {
+            let member_name =
+                Name::parse(member, Some(&Name::root())).map_err(|err| err.to_string())?;
+
+            records.push(Record::from_rdata(
+                Name::parse(&Self::member_identifier(&member_name)?, Some(&zones_name))
+                    .map_err(|err| err.to_string())?,
+                CATALOG_TTL,
+                RData::PTR(HickoryRData::PTR(member_name)),
+            ));
+        }
+
+        // Build authority instance
+        let mut record_sets: BTreeMap<RrKey, RecordSet> = BTreeMap::new();
+
+        for record in &records {
+            record_sets
+                .entry(RrKey::new(
+                    LowerName::new(record.name()),
+                    record.record_type(),
+                ))
+                .or_insert_with(|| RecordSet::new(record.name(), record.record_type(), serial))
+                .insert(record.to_owned(), serial);
+        }
+
+        let authority = DNSAuthority::new(origin.to_owned(), record_sets, ZoneType::Primary, true)?;
+
+        Ok((records, authority))
+    }
+
+    fn member_identifier(member_name: &Name) -> Result<String, String> {
+        // Notice: member identifiers must be unique and stable, thus they are derived from \
+        //   member zone names, using the SHA-1 of their wire format (as suggested by RFC 9432). \
+        //   This makes collisions between two members practically impossible, which would \
+        //   otherwise make secondaries drop or mix up member zones.
+        let member_wire = member_name
+            .to_lowercase()
+            .to_bytes()
+            .map_err(|err| err.to_string())?;
+        let member_digest = DigestType::SHA1
+            .hash(&member_wire)
+            .map_err(|err| err.to_string())?;
+
+        Ok(member_digest
+            .as_ref()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect())
+    }
+
+    fn transfer_message(
+        request: &Request,
+        code: ResponseCode,
+        records: &[&Record],
+    ) -> Option<Vec<u8>> {
+        let mut header = Header::response_from_request(request.header());
+
+        header.set_response_code(code);
+        header.set_authoritative(code == ResponseCode::NoError);
+
+        let mut message = Message::new();
+
+        message
+            .set_header(header)
+            .add_query(request.query().original().to_owned())
+            .add_answers(records.iter().map(|record| (*record).to_owned()));
+
+        match message.to_vec() {
+            Ok(message) => Some(message),
+            Err(err) => {
+                error!("could not encode catalog zone transfer message: {}", err);
+
+                None
+            }
+        }
+    }
+}
@@ -11,7 +11,7 @@ use hickory_resolver::Resolver;
 use std::collections::HashMap;
 use std::net::ToSocketAddrs;
 use std::ops::Deref;
-use std::sync::RwLock;
+use std::sync::{Arc, RwLock};
 use std::thread;
 use std::time::{Duration, Instant, SystemTime};
 
@@ -24,11 +24,11 @@ lazy_static! {
     pub static ref DNS_FLATTEN: DNSFlatten = DNSFlattenBuilder::new();
 }
 
-struct DNSFlattenBuilder;
+pub struct DNSFlattenBuilder;
 
 pub struct DNSFlatten {
     registry: RwLock<HashMap<DNSFlattenRegistryKey, DNSFlattenEntry>>,
-    resolver: Resolver,
+    resolver: RwLock<Arc<Resolver>>,
 }
 
 pub struct DNSFlattenBootstrapBuilder;
@@ -56,15 +56,18 @@ impl DNSFlattenBuilder {
     fn new() -> DNSFlatten {
         DNSFlatten {
             registry: RwLock::new(HashMap::new()),
-            resolver: Self::build_resolver(),
+            resolver: RwLock::new(Arc::new(
+                Self::build_resolver(&APP_CONF.load().dns.flatten.resolvers)
+                    .expect("cannot acquire dns flatten resolver"),
+            )),
         }
     }
 
-    fn build_resolver() -> Resolver {
+    pub fn build_resolver(resolvers: &[String]) -> Result<Resolver, String> {
         // Make resolver configuration
         let mut resolver_config = ResolverConfig::new();
 
-        for resolver in &APP_CONF.dns.flatten.resolvers {
+        for resolver in resolvers {
             let socket_address_string;
 
             if resolver.contains(":") == true {
@@ -81,7 +84,7 @@ impl DNSFlattenBuilder {
             //   configuration)
             let socket_addresses = socket_address_string
                 .to_socket_addrs()
-                .expect("invalid dns resolver address");
+                .map_err(|err| format!("invalid dns resolver address: {}: {}", resolver, err))?;
 
             // Append listed name servers to list of resolvers
             for socket_address in socket_addresses {
@@ -104,8 +107,7 @@ impl DNSFlattenBuilder {
         resolver_options.use_hosts_file = false;
 
         // Build resolver instance
-        Resolver::new(resolver_config, resolver_options)
-            .expect("cannot acquire dns flatten resolver")
+        Resolver::new(resolver_config, resolver_options).map_err(|err| err.to_string())
     }
 }
 
@@ -125,6 +127,10 @@ impl DNSFlattenMaintainBuilder {
 }
 
 impl DNSFlatten {
+    pub fn swap_resolver(&self, resolver: Resolver) {
+        *self.resolver.write().unwrap() = Arc::new(resolver);
+    }
+
     pub fn pass(
         &self,
         record_type: RecordType,
@@ -195,19 +201,20 @@ impl DNSFlatten {
     ) {
         let name = registry_key.0.to_str();
 
+        // Acquire current resolver (it may get swapped upon configuration reload)
+        let resolver = self.resolver.read().unwrap().clone();
+
         // Convert each value type into its string representation
         let values: Result<Vec<String>, ResolveError> = match registry_key.1 {
-            RecordType::A => self
-                .resolver
+            RecordType::A => resolver
                 .ipv4_lookup(name)
                 .map(|values| values.iter().map(|value| value.to_string()).collect()),
-            RecordType::AAAA => self
-                .resolver
+            RecordType::AAAA => resolver
                 .ipv6_lookup(name)
                 .map(|values| values.iter().map(|value| value.to_string()).collect()),
             RecordType::MX => {
                 // Format as `{priority} {exchange}`, eg. `10 inbound.crisp.email`
-                self.resolver.mx_lookup(name).map(|values| {
+                resolver.mx_lookup(name).map(|values| {
                     values
                         .iter()
                         .map(|value| format!("{} {}", value.preference(), value.exchange()))
@@ -216,7 +223,7 @@ impl DNSFlatten {
             }
             RecordType::TXT => {
                 // Assemble all TXT data segments
-                self.resolver.txt_lookup(name).map(|values| {
+                resolver.txt_lookup(name).map(|values| {
                     values
                         .iter()
                         .map(|value_chunks| {
@@ -229,16 +236,13 @@ impl DNSFlatten {
                         .collect()
                 })
             }
-            RecordType::CAA => self
-                .resolver
-                .lookup(name, HickoryRecordType::CAA)
-                .map(|values| {
-                    values
-                        .record_iter()
-                        .filter_map(|record| record.data())
-                        .map(|data| data.to_string())
-                        .collect()
-                }),
+            RecordType::CAA => resolver.lookup(name, HickoryRecordType::CAA).map(|values| {
+                values
+                    .record_iter()
+                    .filter_map(|record| record.data())
+                    .map(|data| data.to_string())
+                    .collect()
+            }),
             RecordType::PTR | RecordType::CNAME => {
                 // Unsupported types (flatten to nothing)
                 Ok(Vec::new())
@@ -5,24 +5,31 @@
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
 use hickory_proto::op::header::Header;
-use hickory_proto::op::{LowerQuery, MessageType, OpCode, ResponseCode};
-use hickory_proto::rr::{LowerName, Name, Record, RecordType as HickoryRecordType};
+use hickory_proto::op::{LowerQuery, Message, MessageType, OpCode, ResponseCode};
+use hickory_proto::rr::{LowerName, Name, RData, Record, RecordType as HickoryRecordType};
+use hickory_proto::serialize::binary::{BinDecodable, BinDecoder, BinEncodable, BinEncoder};
 use hickory_server::authority::{
     AuthLookup, Authority, LookupOptions, MessageRequest, MessageResponseBuilder,
 };
-use hickory_server::server::{Request, RequestHandler, ResponseHandler, ResponseInfo};
+use hickory_server::server::{Protocol, Request, RequestHandler, ResponseHandler, ResponseInfo};
 use hickory_server::store::in_memory::InMemoryAuthority;
 use rand::seq::SliceRandom;
 use rand::thread_rng;
-use std::collections::HashMap;
 use std::io::Error;
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
 
+use super::catalog::DNS_CATALOG;
 use super::code::CodeName;
 use super::flatten::DNS_FLATTEN;
 use super::health::{DNSHealth, DNSHealthStatus};
 use super::metrics::{MetricsValue, METRICS_STORE};
+use super::ratelimit::DNS_RATE_LIMIT;
 use super::record::{RecordName, RecordType};
+use super::registry::DNS_REGISTRY;
+use super::responder::DNSResponder;
+use super::secondary::DNS_SECONDARY;
+use super::update::DNSUpdate;
 use super::zone::ZoneName;
 use crate::geo::locate::Locator;
 use crate::geo::region::RegionCode;
@@ -30,12 +37,15 @@ use crate::store::store::{StoreAccessOrigin, StoreError, StoreRecord};
 use crate::APP_CONF;
 use crate::APP_STORE;
 
+const CNAME_CHASE_DEPTH_MAX: usize = 8;
+const UDP_PAYLOAD_SIZE_MINIMUM: u16 = 512;
+const MESSAGE_HEADER_SIZE: usize = 12;
+const EDNS_RECORD_SIZE: usize = 11;
+
 pub type DNSAuthority = InMemoryAuthority;
 type DNSResponse = Result<ResponseInfo, Error>;
 
-pub struct DNSHandler {
-    authorities: HashMap<LowerName, DNSAuthority>,
-}
+pub struct DNSHandler;
 
 #[async_trait::async_trait]
 impl RequestHandler for DNSHandler {
@@ -64,13 +74,79 @@ impl RequestHandler for DNSHandler {
 
 impl DNSHandler {
     pub fn new() -> Self {
-        DNSHandler {
-            authorities: HashMap::new(),
-        }
+        DNSHandler {}
     }
 
-    pub fn add_authority(&mut self, name: LowerName, authority: DNSAuthority) {
-        self.authorities.insert(name, authority);
+    pub async fn handle_raw(
+        &self,
+        message: &[u8],
+        src: SocketAddr,
+        protocol: Protocol,
+    ) -> Vec<Vec<u8>> {
+        // Decode request message
+        let mut decoder = BinDecoder::new(message);
+
+        let request = match MessageRequest::read(&mut decoder) {
+            Ok(request) => Request::new(request, src, protocol),
+            Err(err) => {
+                debug!("could not decode dns request from: {}: {}", src.ip(), err);
+
+                // Reply with a format error? (only if the request header could be read)
+                return match Header::read(&mut BinDecoder::new(message)) {
+                    Ok(header) if header.message_type() == MessageType::Query => {
+                        Message::error_msg(header.id(), header.op_code(), ResponseCode::FormErr)
+                            .to_vec()
+                            .into_iter()
+                            .collect()
+                    }
+                    _ => Vec::new(),
+                };
+            }
+        };
+
+        // Notice: all responses are made of a single message, except for zone transfers.
+        let response = match (request.message_type(), request.op_code()) {
+            (MessageType::Query, OpCode::Update) => {
+                // Notice: dynamic updates need the raw message, as TSIG signatures are \
+                //   computed over the exact bytes that were received.
+                let origin = DNS_REGISTRY
+                    .get_authority(request.query().name())
+                    .map(|authority| authority.origin().into());
+
+                DNSUpdate::handle(&request, message, origin.as_ref()).await
+            }
+            (MessageType::Query, OpCode::Notify) => DNS_SECONDARY.handle_notify(&request),
+            (MessageType::Query, OpCode::Query) if DNS_CATALOG.is_transfer(request.query()) => {
+                return DNS_CATALOG.handle_transfer(&request);
+            }
+            (MessageType::Response, _) => {
+                // Never answer to responses, as this could lead to response loops
+                warn!(
+                    "dropped a response as a request from identifier: {}",
+                    request.id()
+                );
+
+                None
+            }
+            _ => {
+                let responder = DNSResponder::new(protocol);
+
+                RequestHandler::handle_request(self, &request, responder.clone()).await;
+
+                // Apply response rate limiting? (only over UDP, as other transports cannot be \
+                //   used for reflection attacks)
+                match (responder.take(), protocol) {
+                    (Some(response), Protocol::Udp) => DNS_RATE_LIMIT.limit(
+                        &request,
+                        response,
+                        self.find_zone(request.query().name()),
+                    ),
+                    (response, _) => response,
+                }
+            }
+        };
+
+        response.into_iter().collect()
     }
 
     async fn handle_request<R: ResponseHandler>(
@@ -116,7 +192,52 @@ impl DNSHandler {
         // Notice: if zone cannot be found, then reject straight away.
         // Notice: since we checked the status of the unwrapped query variable, this is panic-safe.
         let query = request.query();
-        let authority_lookup = self.find_authority_recurse(query.name());
+        let authority_lookup = DNS_REGISTRY.find_authority(query.name());
+
+        // Query is for the catalog zone? (only served to clients allowed to transfer it)
+        if let Some((catalog_name, catalog_authority)) = DNS_CATALOG.find_authority(query.name()) {
+            if !DNS_CATALOG.is_allowed(&request.src().ip()) {
+                return self
+                    .lookup_no_authority(responder, request, header, query)
+                    .await;
+            }
+
+            return self
+                .lookup_memory(
+                    responder,
+                    request,
+                    header,
+                    query,
+                    &catalog_name,
+                    Some(catalog_authority),
+                )
+                .await;
+        }
+
+        // Query is for a secondary zone? (serve it from transferred data)
+        // Notice: a secondary zone may be nested in a primary zone, or the other way around, \
+        //   thus the closest zone wins.
+        if let Some((secondary_name, secondary_authority)) =
+            DNS_SECONDARY.find_authority(query.name())
+        {
+            let is_closest = authority_lookup
+                .as_ref()
+                .map(|authority| secondary_name.num_labels() > authority.origin().num_labels())
+                .unwrap_or(true);
+
+            if is_closest {
+                return self
+                    .lookup_memory(
+                        responder,
+                        request,
+                        header,
+                        query,
+                        &secondary_name,
+                        secondary_authority,
+                    )
+                    .await;
+            }
+        }
 
         if authority_lookup.is_none() == true {
             return self
@@ -162,7 +283,7 @@ impl DNSHandler {
 
         // #4. Resolve from remote store
         // Notice: this is used to serve all records set with the HTTP API.
-        return match Self::records_from_store(authority, &zone_name, request.src().ip(), query)
+        return match Self::records_from_store(&authority, &zone_name, request.src().ip(), query)
             .await
         {
             Ok(records_remote) => {
@@ -222,13 +343,84 @@ impl DNSHandler {
         header.set_response_code(ResponseCode::Refused);
 
         // Authority not found response dispatch
-        Self::dispatch_response(responder, request, header, None, None).await
+        Self::dispatch_response(responder, request, header, None, None, None).await
+    }
+
+    async fn lookup_memory<R: ResponseHandler>(
+        &self,
+        responder: R,
+        request: &Request,
+        mut header: Header,
+        query: &LowerQuery,
+        name: &LowerName,
+        authority: Option<Arc<DNSAuthority>>,
+    ) -> DNSResponse {
+        let zone_name = ZoneName::from_hickory(name);
+
+        // In-memory zone is not available? (eg. secondary zone not transferred yet, or expired)
+        let authority = match authority {
+            Some(authority) => authority,
+            None => {
+                warn!("in-memory zone not available for query: {:?}", query);
+
+                Self::stamp_header(request, &mut header, ResponseCode::ServFail, &zone_name);
+
+                return Self::dispatch_response(responder, request, header, None, None, None).await;
+            }
+        };
+
+        let soa_records = authority.soa().await.unwrap_or(AuthLookup::Empty);
+        let soa_records_vec = soa_records.iter().collect();
+
+        match authority
+            .search(request.request_info(), LookupOptions::default())
+            .await
+        {
+            Ok(records) if !records.is_empty() => {
+                debug!("found records for query from in-memory zone: {:?}", query);
+
+                Self::serve_response_records(
+                    responder,
+                    request,
+                    header,
+                    &zone_name,
+                    records.iter().collect(),
+                    soa_records_vec,
+                )
+                .await
+            }
+            result => {
+                // Notice: names with no data for the requested type are not NXDOMAIN
+                let code = match result {
+                    Err(ref err) if err.is_nx_domain() => ResponseCode::NXDomain,
+                    Err(ref err) if err.is_refused() => ResponseCode::Refused,
+                    _ => ResponseCode::NoError,
+                };
+
+                debug!(
+                    "did not find records for query: {:?} in in-memory zone",
+                    query
+                );
+
+                Self::stamp_header(request, &mut header, code, &zone_name);
+
+                Self::dispatch_response(
+                    responder,
+                    request,
+                    header,
+                    None,
+                    Some(soa_records_vec),
+                    None,
+                )
+                .await
+            }
+        }
     }
 
     async fn lookup_local<'a, R: ResponseHandler>(
         &self,
         responder: R,
-        request: &MessageRequest,
+        request: &Request,
         header: Header,
         query: &LowerQuery,
         zone_name: Option<ZoneName>,
@@ -251,7 +443,7 @@ impl DNSHandler {
     async fn lookup_remote_some<'a, R: ResponseHandler>(
         &self,
         responder: R,
-        request: &MessageRequest,
+        request: &Request,
         header: Header,
         query: &LowerQuery,
         zone_name: Option<ZoneName>,
@@ -312,7 +504,7 @@ impl DNSHandler {
         Self::stamp_header(request, &mut header, response_error, &zone_name);
 
         // Dispatch empty records response
-        Self::dispatch_response(responder, request, header, None, Some(soa_records)).await
+        Self::dispatch_response(responder, request, header, None, Some(soa_records), None).await
     }
 
     async fn lookup_remote_fail<'a, R: ResponseHandler>(
@@ -330,7 +522,7 @@ impl DNSHandler {
         Self::stamp_header(request, &mut header, code, &zone_name);
 
         // Dispatch error response
-        Self::dispatch_response(responder, request, header, None, Some(soa_records)).await
+        Self::dispatch_response(responder, request, header, None, Some(soa_records), None).await
     }
 
     fn abort(&self, code: ResponseCode) -> DNSResponse {
@@ -347,6 +539,7 @@ impl DNSHandler {
         header: Header,
         records: Option<Vec<&'a Record>>,
         soa_records: Option<Vec<&Record>>,
+        additional_records: Option<Vec<&Record>>,
     ) -> Result<ResponseInfo, Error> {
         let mut records = records.unwrap_or(vec![]);
         let has_records = !records.is_empty();
@@ -354,8 +547,15 @@ impl DNSHandler {
         // Add records to response?
         if has_records == true {
             // Randomize records order, as most DNS servers do to balance eg. IP resource usage
-            if records.len() > 1 {
-                records.shuffle(&mut thread_rng());
+            // Notice: records are only shuffled within their record set, so that CNAME chains \
+            //   keep their order.
+            for records_set in records.chunk_by_mut(|record_a, record_b| {
+                record_a.name() == record_b.name()
+                    && record_a.record_type() == record_b.record_type()
+            }) {
+                if records_set.len() > 1 {
+                    records_set.shuffle(&mut thread_rng());
+                }
             }
         }
 
@@ -373,7 +573,7 @@ impl DNSHandler {
             records,
             &[],
             soa_records.unwrap_or(vec![]),
-            &[],
+            additional_records.unwrap_or(vec![]),
         );
 
         trace!("query response: {:?}", response_message);
@@ -408,7 +608,7 @@ impl DNSHandler {
 
     async fn serve_response_records<'a, 'b, R: ResponseHandler>(
         responder: R,
-        request: &MessageRequest,
+        request: &Request,
         mut header: Header,
         zone_name: &Option<ZoneName>,
         records: Vec<&'a Record>,
@@ -416,23 +616,184 @@ impl DNSHandler {
     ) -> DNSResponse {
         Self::stamp_header(request, &mut header, ResponseCode::NoError, zone_name);
 
-        Self::dispatch_response(responder, request, header, Some(records), Some(soa_records)).await
+        // Acquire addresses of in-zone targets (served in the additional section)
+        let additional_records = Self::records_additional(request, &records, &soa_records).await;
+
+        Self::dispatch_response(
+            responder,
+            request,
+            header,
+            Some(records),
+            Some(soa_records),
+            Some(additional_records.iter().collect()),
+        )
+        .await
     }
 
-    fn find_authority_recurse(&self, name: &LowerName) -> Option<&DNSAuthority> {
-        let authority = self.authorities.get(name);
+    async fn records_additional(
+        request: &Request,
+        records: &[&Record],
+        soa_records: &[&Record],
+    ) -> Vec<Record> {
+        let mut additional_records = Vec::new();
+
+        // Acquire targets from answer records (de-duplicated, as eg. multiple MX values could \
+        //   share the same target)
+        let mut targets: Vec<LowerName> = Vec::new();
+
+        for record in records {
+            let target = match record.data() {
+                Some(RData::MX(mx)) => mx.exchange(),
+                Some(RData::SRV(srv)) => srv.target(),
+                Some(RData::NS(ns)) => &ns.0,
+                _ => continue,
+            };
+
+            let target = Self::name_fqdn(target);
+
+            if !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+
+        if targets.is_empty() {
+            return additional_records;
+        }
 
-        if authority.is_some() {
-            return authority;
+        // Acquire size budget for additional records
+        // Notice: the additional section must fit in the UDP payload size announced by the \
+        //   client, otherwise the response would get truncated. Sizes are measured without \
+        //   name compression, thus this errs on the safe side.
+        let mut size_left = if let Protocol::Udp = request.protocol() {
+            request
+                .edns()
+                .map(|edns| edns.max_payload().max(UDP_PAYLOAD_SIZE_MINIMUM))
+                .unwrap_or(UDP_PAYLOAD_SIZE_MINIMUM) as usize
         } else {
-            let name = name.base_name();
+            u16::MAX as usize
+        };
+
+        let size_used = MESSAGE_HEADER_SIZE
+            + Self::size_of(request.query().original())
+            + records
+                .iter()
+                .map(|record| Self::size_of(*record))
+                .sum::<usize>()
+            + if records.is_empty() {
+                soa_records
+                    .iter()
+                    .map(|record| Self::size_of(*record))
+                    .sum::<usize>()
+            } else {
+                0
+            }
+            + if request.edns().is_some() {
+                EDNS_RECORD_SIZE
+            } else {
+                0
+            };
+
+        size_left = size_left.saturating_sub(size_used);
+
+        for target in &targets {
+            for record_type in [HickoryRecordType::A, HickoryRecordType::AAAA] {
+                let target_records =
+                    Self::records_additional_lookup(request.src().ip(), target, record_type).await;
+
+                // Notice: record sets are never split, as resolvers would otherwise cache an \
+                //   incomplete set of addresses.
+                let target_size = target_records.iter().map(Self::size_of).sum::<usize>();
+
+                if target_size > size_left {
+                    debug!("additional records for: {} do not fit in response", target);
 
-            if !name.is_root() {
-                return self.find_authority_recurse(&name);
+                    continue;
+                }
+
+                size_left -= target_size;
+
+                additional_records.extend(target_records);
             }
         }
 
-        None
+        additional_records
+    }
+
+    async fn records_additional_lookup(
+        source: IpAddr,
+        target: &LowerName,
+        record_type: HickoryRecordType,
+    ) -> Vec<Record> {
+        // Target lives in a zone served from the store?
+        if let Some(authority) = Self::chase_authority(target) {
+            let zone_name = ZoneName::from_hickory(authority.origin());
+
+            return Self::records_from_store_name(
+                &authority,
+                &zone_name,
+                source,
+                target,
+                &record_type,
+                &RecordType::from_hickory(&record_type),
+            )
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|record| record.record_type() == record_type)
+            .collect();
+        }
+
+        // Target lives in a secondary zone? (served from memory)
+        if let Some((_, Some(authority))) = DNS_SECONDARY.find_authority(target) {
+            return authority
+                .lookup(target, record_type, LookupOptions::default())
+                .await
+                .map(|records| {
+                    records
+                        .iter()
+                        .filter(|record| record.record_type() == record_type)
+                        .cloned()
+                        .collect()
+                })
+                .unwrap_or_default();
+        }
+
+        Vec::new()
+    }
+
+    fn size_of<T: BinEncodable>(value: &T) -> usize {
+        let mut buffer = Vec::new();
+
+        match value.emit(&mut BinEncoder::new(&mut buffer)) {
+            Ok(_) => buffer.len(),
+            Err(_) => usize::MAX,
+        }
+    }
+
+    fn name_fqdn(name: &Name) -> LowerName {
+        // Notice: names built from store values do not carry their trailing dot
+        let mut name = name.to_owned();
+
+        name.set_fqdn(true);
+
+        LowerName::new(&name)
+    }
+
+    fn find_zone(&self, name: &LowerName) -> Option<LowerName> {
+        // Notice: the closest zone wins, amongst all zones that we are authoritative for.
+        DNS_CATALOG
+            .find_authority(name)
+            .map(|(zone, _)| zone)
+            .into_iter()
+            .chain(DNS_SECONDARY.find_authority(name).map(|(zone, _)| zone))
+            .chain(
+                DNS_REGISTRY
+                    .find_authority(name)
+                    .map(|authority| authority.origin().to_owned()),
+            )
+            .max_by_key(|zone| zone.num_labels())
     }
 
     async fn records_from_store(
@@ -449,60 +810,230 @@ impl DNSHandler {
             METRICS_STORE.stack(zone_name, MetricsValue::QueryType(&record_type));
         }
 
-        // Attempt with requested domain
-        let mut records = Self::records_from_store_attempt(
+        // Lookup records for requested domain
+        let mut records = Self::records_from_store_name(
             authority,
-            source,
             zone_name,
-            &query_name,
-            &query_name,
+            source,
+            query_name,
             &query_type,
             &record_type,
         )
         .await?;
 
-        // Check if 'records' is empty
-        let is_records_empty = if let Some(ref records_inner) = records {
-            records_inner.is_empty()
-        } else {
-            records.is_none()
-        };
+        // Follow CNAME chain? (if it stays within zones we are authoritative for)
+        // Notice: this saves resolvers from issuing another query for each CNAME target.
+        if let (Some(records_inner), Some(record_type_inner)) =
+            (records.as_mut(), record_type.as_ref())
+        {
+            if record_type_inner != &RecordType::CNAME {
+                Self::records_from_store_chase(
+                    source,
+                    query_name,
+                    &query_type,
+                    &record_type,
+                    records_inner,
+                )
+                .await?;
+            }
+        }
+
+        Ok(records)
+    }
+
+    async fn records_from_store_name(
+        authority: &DNSAuthority,
+        zone_name: &Option<ZoneName>,
+        source: IpAddr,
+        query_name: &LowerName,
+        query_type: &HickoryRecordType,
+        record_type: &Option<RecordType>,
+    ) -> Result<Option<Vec<Record>>, ResponseCode> {
+        // Attempt with requested domain
+        let mut records = Self::records_from_store_attempt(
+            authority,
+            source,
+            zone_name,
+            query_name,
+            query_name,
+            query_type,
+            record_type,
+        )
+        .await?;
 
-        // Attempt with wildcard domain? (records empty)
-        if is_records_empty == true {
+        // Attempt with wildcard domains? (name does not exist)
+        // Notice: a name that exists (even with no record for the requested type) is never \
+        //   synthesized from a wildcard, as per RFC 4592.
+        if records.is_none() {
             debug!(
-                "got empty records from store, attempting wildcard for query: {:?}",
-                query
+                "name does not exist in store, attempting wildcards for name: {}",
+                query_name
             );
 
-            if let Some(base_name) = query_name.to_string().splitn(2, ".").nth(1) {
-                let wildcard_name_string = format!("*.{}", base_name);
+            records = Self::records_from_store_wildcard(
+                authority,
+                zone_name,
+                source,
+                query_name,
+                query_type,
+                record_type,
+            )
+            .await?;
+        }
 
-                if let Ok(wildcard_name) = Name::parse(&wildcard_name_string, Some(&Name::new())) {
-                    let wildcard_name_lower = LowerName::new(&wildcard_name);
+        Ok(records)
+    }
 
-                    if &wildcard_name_lower != query_name {
-                        let records_wildcard = Self::records_from_store_attempt(
-                            authority,
-                            source,
-                            &zone_name,
-                            &query_name,
-                            &wildcard_name_lower,
-                            &query_type,
-                            &record_type,
-                        )
-                        .await?;
+    async fn records_from_store_chase(
+        source: IpAddr,
+        query_name: &LowerName,
+        query_type: &HickoryRecordType,
+        record_type: &Option<RecordType>,
+        records: &mut Vec<Record>,
+    ) -> Result<(), ResponseCode> {
+        let mut chased_names = vec![query_name.to_owned()];
+        let mut hop_start = 0;
+
+        while chased_names.len() <= CNAME_CHASE_DEPTH_MAX {
+            // Acquire CNAME target from last hop (a name holds at most one CNAME)
+            let mut targets = records[hop_start..]
+                .iter()
+                .filter(|record| record.record_type() == HickoryRecordType::CNAME)
+                .filter_map(|record| record.data().and_then(|data| data.as_cname()))
+                .map(|target| Self::name_fqdn(&target.0));
+
+            let target = match (targets.next(), targets.next()) {
+                (Some(target), None) => target,
+                _ => break,
+            };
 
-                        // Assign non-none wildcard records? (retain any NOERROR from 'records')
-                        if records_wildcard.is_none() == false {
-                            records = records_wildcard
-                        }
+            if chased_names.contains(&target) {
+                warn!(
+                    "cname loop detected on: {} for query: {}",
+                    target, query_name
+                );
+
+                break;
+            }
+
+            // Target is out of our zones? (let the resolver continue from there)
+            let authority = match Self::chase_authority(&target) {
+                Some(authority) => authority,
+                None => break,
+            };
+
+            let zone_name = ZoneName::from_hickory(authority.origin());
+
+            debug!("chasing cname to: {} for query: {}", target, query_name);
+
+            // Notice: Geo-DNS and health checks apply to each hop, as the target records are \
+            //   looked up as if they were queried directly.
+            let hop_records = Self::records_from_store_name(
+                &authority,
+                &zone_name,
+                source,
+                &target,
+                query_type,
+                record_type,
+            )
+            .await?;
+
+            chased_names.push(target);
+
+            match hop_records {
+                Some(hop_records) if !hop_records.is_empty() => {
+                    hop_start = records.len();
+
+                    records.extend(hop_records);
+                }
+                _ => break,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn chase_authority(name: &LowerName) -> Option<Arc<DNSAuthority>> {
+        let authority = DNS_REGISTRY.find_authority(name)?;
+
+        // Notice: names that belong to a closer secondary or catalog zone are not chased, as \
+        //   those zones are served from memory.
+        let is_shadowed = DNS_CATALOG.find_authority(name).is_some()
+            || DNS_SECONDARY
+                .find_authority(name)
+                .map(|(secondary_name, _)| {
+                    secondary_name.num_labels() > authority.origin().num_labels()
+                })
+                .unwrap_or(false);
+
+        if is_shadowed {
+            None
+        } else {
+            Some(authority)
+        }
+    }
+
+    async fn records_from_store_wildcard(
+        authority: &DNSAuthority,
+        zone_name: &Option<ZoneName>,
+        source: IpAddr,
+        query_name: &LowerName,
+        query_type: &HickoryRecordType,
+        record_type: &Option<RecordType>,
+    ) -> Result<Option<Vec<Record>>, ResponseCode> {
+        let origin = authority.origin();
+
+        // Walk up ancestors of the query name, until the closest encloser is found (ie. the \
+        //   deepest ancestor that exists); its wildcard child is the source of synthesis.
+        // Notice: the wildcard child of each ancestor is attempted on the way up, as an \
+        //   ancestor that has a wildcard child exists, and thus is the closest encloser.
+        let mut encloser = query_name.base_name();
+
+        while origin.zone_of(&encloser) {
+            let wildcard_name = Name::parse("*", Some(&Name::from(&encloser)))
+                .map(|wildcard_name| LowerName::new(&wildcard_name));
+
+            if let Ok(wildcard_name) = wildcard_name {
+                if &wildcard_name != query_name {
+                    let records_wildcard = Self::records_from_store_attempt(
+                        authority,
+                        source,
+                        zone_name,
+                        query_name,
+                        &wildcard_name,
+                        query_type,
+                        record_type,
+                    )
+                    .await?;
+
+                    // Wildcard exists? (answer from it, even if it has no record for type)
+                    if records_wildcard.is_some() {
+                        return Ok(records_wildcard);
                     }
                 }
             }
+
+            // Closest encloser reached? (its wildcard child does not exist, thus name does not \
+            //   exist either)
+            if &encloser == origin {
+                break;
+            }
+
+            if let (Some(zone_name), Some(encloser_name)) = (
+                zone_name.as_ref(),
+                RecordName::from_hickory(origin, &encloser),
+            ) {
+                if Self::check_name_exists(zone_name, &encloser_name, StoreAccessOrigin::External)
+                    .await?
+                {
+                    break;
+                }
+            }
+
+            encloser = encloser.base_name();
         }
 
-        Ok(records)
+        Ok(None)
     }
 
     async fn records_from_store_attempt(
@@ -603,7 +1134,7 @@ impl DNSHandler {
                     return Ok(Some(records));
                 }
 
-                // No record found, exhaust all record types to check if name exists
+                // No record found, check if name exists (through its types index)
                 // Notice: a DNS server must return NOERROR if name exists, else NXDOMAIN
                 if Self::check_name_exists(&zone_name, &record_name, StoreAccessOrigin::External)
                     .await?
@@ -738,8 +1269,10 @@ impl DNSHandler {
 
             // Not blackholed? (push values)
             if is_blackholed == false {
-                // Acquire record TTL
-                let record_ttl = record.ttl.unwrap_or(APP_CONF.dns.record_ttl);
+                // Acquire record TTL (falls back to zone default TTL)
+                let record_ttl = record
+                    .ttl
+                    .unwrap_or_else(|| APP_CONF.load().dns.zone_record_ttl(zone_name.to_str()));
 
                 // Aggregate values (healthy ones only for DNS health check)
                 let mut prepared_values = values
@@ -861,30 +1394,34 @@ impl DNSHandler {
         record_name: &RecordName,
         origin: StoreAccessOrigin,
     ) -> Result<bool, ResponseCode> {
-        // Exhaust all record types
-        for record_type in RecordType::list_choices() {
-            // A record exists for name and type?
-            // Notice: instead of performing a simple exist check, we acquire full record data, \
-            //   as this lets us use the local store and therefore prevent non-existing domain \
-            //   attacks on the remote store.
-            match APP_STORE
-                .get(zone_name, record_name, &record_type, origin)
-                .await
-            {
-                Ok(_) => {
-                    // Record exists for name and type; abort there.
-                    return Ok(true);
-                }
-                Err(StoreError::Disconnected) => {
-                    // Store is down, consider it as a DNS server failure (this avoids polluting \
-                    //   recursive DNS caches); abort there.
-                    return Err(ResponseCode::ServFail);
-                }
-                _ => {}
+        // Any record exists for name?
+        // Notice: the types index of the name is cached in the local store, which prevents \
+        //   non-existing domain attacks on the remote store.
+        match APP_STORE.get_types(zone_name, record_name, origin).await {
+            Ok(record_types) if !record_types.is_empty() => {
+                // Record exists for name; abort there.
+                return Ok(true);
+            }
+            Err(StoreError::Disconnected) => {
+                // Store is down, consider it as a DNS server failure (this avoids polluting \
+                //   recursive DNS caches); abort there.
+                return Err(ResponseCode::ServFail);
             }
+            _ => {}
         }
 
-        // No alternate record found, consider name as non-existing.
-        return Ok(false);
+        // No record found, though the name may still exist if names exist below it (ie. the name \
+        //   is an empty non-terminal, which must be answered with NOERROR, as per RFC 8020).
+        match APP_STORE
+            .has_descendants(zone_name, record_name, origin)
+            .await
+        {
+            Ok(true) => Ok(true),
+            Err(StoreError::Disconnected) => Err(ResponseCode::ServFail),
+            _ => {
+                // No alternate record found, consider name as non-existing.
+                Ok(false)
+            }
+        }
     }
 }
@@ -88,25 +88,58 @@ impl DNSHealthBuilder {
 
 impl DNSHealth {
     pub fn run(&self) {
-        let interval_duration = Duration::from_secs(APP_CONF.dns.health.check_interval);
+        loop {
+            // Notice: health check settings are read on each run, as they can change upon \
+            //   configuration reload.
+            let (check_enable, interval_duration) = {
+                let conf = APP_CONF.load();
+
+                (
+                    conf.dns.health.check_enable,
+                    Duration::from_secs(conf.dns.health.check_interval),
+                )
+            };
 
-        debug!("scheduled dns health checks every: {:?}", interval_duration);
+            if check_enable {
+                info!("running a dns health check operation...");
 
-        loop {
-            info!("running a dns health check operation...");
+                // Hold on 1 second
+                thread::sleep(Duration::from_secs(1));
 
-            // Hold on 1 second
-            thread::sleep(Duration::from_secs(1));
+                Self::run_checks();
 
-            Self::run_checks();
+                info!("ran dns health check operation");
+            }
 
-            info!("ran dns health check operation");
+            debug!(
+                "scheduled next dns health checks in: {:?}",
+                interval_duration
+            );
 
             // Hold for next check run
             thread::sleep(interval_duration);
         }
     }
 
+    pub fn reload() {
+        // Forget about dead records that are not health checked anymore (otherwise, they \
+        //   would never be checked again, and thus would remain dead forever)
+        let conf = APP_CONF.load();
+
+        HEALTH_DEAD_REGISTER
+            .write()
+            .unwrap()
+            .retain(|(zone_name, record_name, _)| {
+                conf.dns.health.check_enable
+                    && conf
+                        .dns
+                        .health
+                        .http
+                        .iter()
+                        .any(|domain| &domain.zone == zone_name && &domain.name == record_name)
+            });
+    }
+
     pub fn status(
         zone_name: &ZoneName,
         record_type: &RecordType,
@@ -114,7 +147,7 @@ impl DNSHealth {
         record_value: &RecordValue,
     ) -> DNSHealthStatus {
         // Check if record value is seen as dead?
-        if APP_CONF.dns.health.check_enable == true && Self::should_check_record(record_type) {
+        if APP_CONF.load().dns.health.check_enable && Self::should_check_record(record_type) {
             debug!(
                 "checking local dns health status for record: {:?} at chain: {:?} / {:?} / {:?}",
                 record_type, zone_name, record_name, record_value
@@ -160,7 +193,7 @@ impl DNSHealthHTTP {
     async fn run(notifier: &mut DNSHealthNotify) {
         debug!("running dns health checks for the http protocol...");
 
-        for domain in &APP_CONF.dns.health.http {
+        for domain in &APP_CONF.load().dns.health.http {
             Self::check_domain(domain, notifier).await;
         }
 
@@ -544,7 +577,7 @@ impl DNSHealthNotify {
     }
 
     fn dispatch_slack(&self) {
-        if let Some(ref slack_hook_url) = APP_CONF.dns.health.notify.slack_hook_url {
+        if let Some(ref slack_hook_url) = APP_CONF.load().dns.health.notify.slack_hook_url {
             debug!("will dispatch notification to slack hooks for dns health check");
 
             // Build paylaod
@@ -562,7 +595,7 @@ impl DNSHealthNotify {
             };
 
             // Reveal origin replica identifier
-            let replica_origin = format!(" Replica: *{}*.", APP_CONF.server.identifier);
+            let replica_origin = format!(" Replica: *{}*.", APP_CONF.load().server.identifier);
 
             payload.text.push_str(&replica_origin);
             attachment.fallback.push_str(&replica_origin);
@@ -0,0 +1,195 @@
+// Constellation
+//
+// Pluggable authoritative DNS server
+// Copyright: 2026, Valerian Saliou <valerian@valeriansaliou.name>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use actix_web::http::header::{self, CacheControl, CacheDirective, ContentType};
+use actix_web::{rt, web, App, HttpRequest, HttpResponse, HttpServer};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hickory_proto::op::Message;
+use hickory_server::server::Protocol;
+use std::net::{IpAddr, SocketAddr};
+
+use super::handler::DNSHandler;
+use super::tls::DNSTLSCertificate;
+use crate::APP_CONF;
+
+static DNS_MESSAGE_MIME: &str = "application/dns-message";
+static DNS_MESSAGE_SIZE_MAX: usize = 65535;
+
+pub struct DNSHTTPSListenBuilder;
+pub struct DNSHTTPSListen;
+
+#[derive(Deserialize)]
+struct DNSHTTPSQuery {
+    dns: String,
+}
+
+impl DNSHTTPSListenBuilder {
+    pub fn new() -> DNSHTTPSListen {
+        DNSHTTPSListen {}
+    }
+}
+
+impl DNSHTTPSListen {
+    pub fn run(&self) {
+        let runtime = rt::System::new();
+
+        // Prepare the DNS handler
+        let handler = web::Data::new(DNSHandler::new());
+
+        let mut server = HttpServer::new(move || {
+            App::new()
+                .app_data(handler.clone())
+                .route(&APP_CONF.load().dns.https.path, web::get().to(get_query))
+                .route(&APP_CONF.load().dns.https.path, web::post().to(post_query))
+        });
+
+        // Notice: TLS can be disabled, if the DNS-over-HTTPS endpoint is served behind a \
+        //   reverse proxy that terminates TLS itself.
+        let tls_config = if APP_CONF.load().dns.https.tls {
+            Some(DNSTLSCertificate::server_config(&[]).expect("invalid tls certificate"))
+        } else {
+            None
+        };
+
+        for inet in &APP_CONF.load().dns.https.inets {
+            info!("will listen for https on {}", inet);
+
+            server = match tls_config {
+                Some(ref tls_config) => server.bind_rustls_021(inet, (**tls_config).clone()),
+                None => server.bind(inet),
+            }
+            .unwrap_or_else(|_| panic!("https bind failed: {}", inet));
+        }
+
+        info!("listening for dns-over-https connections");
+
+        runtime.block_on(server.run()).unwrap()
+    }
+}
+
+async fn get_query(
+    request: HttpRequest,
+    query: web::Query<DNSHTTPSQuery>,
+    handler: web::Data<DNSHandler>,
+) -> HttpResponse {
+    // Notice: messages are encoded in base64url, whose padding may be omitted (RFC 8484).
+    match URL_SAFE_NO_PAD.decode(query.dns.trim_end_matches('=')) {
+        Ok(message) => handle_message(&request, &message, &handler).await,
+        Err(_) => HttpResponse::BadRequest().finish(),
+    }
+}
+
+async fn post_query(
+    request: HttpRequest,
+    body: web::Bytes,
+    handler: web::Data<DNSHandler>,
+) -> HttpResponse {
+    let content_type = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+
+    if content_type != Some(DNS_MESSAGE_MIME) {
+        return HttpResponse::UnsupportedMediaType().finish();
+    }
+
+    handle_message(&request, &body, &handler).await
+}
+
+async fn handle_message(
+    request: &HttpRequest,
+    message: &[u8],
+    handler: &DNSHandler,
+) -> HttpResponse {
+    if message.is_empty() || message.len() > DNS_MESSAGE_SIZE_MAX {
+        return HttpResponse::BadRequest().finish();
+    }
+
+    let src = match source(request) {
+        Some(src) => src,
+        None => return HttpResponse::BadRequest().finish(),
+    };
+
+    // Notice: only the first response message is sent, as zone transfers are not served over \
+    //   HTTPS (this is the only case where multiple response messages are produced).
+    match handler
+        .handle_raw(message, src, Protocol::Https)
+        .await
+        .into_iter()
+        .next()
+    {
+        Some(response) => {
+            let mut builder = HttpResponse::Ok();
+
+            builder.insert_header(ContentType(DNS_MESSAGE_MIME.parse().unwrap()));
+
+            // Allow HTTP caches to store the response for as long as its shortest TTL
+            if let Some(ttl) = response_ttl(&response) {
+                builder.insert_header(CacheControl(vec![CacheDirective::MaxAge(ttl)]));
+            }
+
+            builder.body(response)
+        }
+        None => HttpResponse::BadRequest().finish(),
+    }
+}
+
+fn source(request: &HttpRequest) -> Option<SocketAddr> {
+    let peer = request.peer_addr()?;
+
+    let is_trusted = |ip: &IpAddr| {
+        APP_CONF
+            .load()
+            .dns
+            .https
+            .trusted_proxies
+            .iter()
+            .any(|network| network.contains(ip))
+    };
+
+    // Resolve client address from the 'X-Forwarded-For' header? (only when sent by a trusted \
+    //   proxy, as it could otherwise be spoofed)
+    // Notice: the header is walked from the right, as each proxy appends the address it \
+    //   received the request from; the first untrusted address is the client address.
+    let mut ip = peer.ip();
+
+    if is_trusted(&ip) {
+        let forwarded_for: Vec<&str> = request
+            .headers()
+            .get_all("X-Forwarded-For")
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .collect();
+
+        for hop in forwarded_for.into_iter().rev() {
+            match hop.trim().parse::<IpAddr>() {
+                Ok(hop) => {
+                    ip = hop;
+
+                    if !is_trusted(&ip) {
+                        break;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+    }
+
+    Some(SocketAddr::new(ip, peer.port()))
+}
+
+fn response_ttl(response: &[u8]) -> Option<u32> {
+    let message = Message::from_vec(response).ok()?;
+
+    // Notice: negative responses are cached as per their SOA record (in the authority section).
+    message
+        .answers()
+        .iter()
+        .chain(message.name_servers())
+        .map(|record| record.ttl())
+        .min()
+}
@@ -4,27 +4,27 @@
 // Copyright: 2018, Valerian Saliou <valerian@valeriansaliou.name>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
-use hickory_proto::rr::rdata as HickoryRData;
-use hickory_proto::rr::record_data::RData;
-use hickory_proto::rr::{LowerName, Name, Record, RecordSet, RecordType, RrKey};
-use hickory_server::authority::ZoneType;
-use hickory_server::server::ServerFuture;
-use std::collections::BTreeMap;
+use hickory_server::server::Protocol;
+use quinn::{Connection, Endpoint, RecvStream, SendStream, ServerConfig, TransportConfig, VarInt};
+use std::convert::TryInto;
+use std::net::SocketAddr;
+use std::sync::Arc;
 use std::time::Duration;
-use tokio::net::{TcpListener, UdpSocket};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::task::JoinSet;
+use tokio::time::timeout;
+use tokio_rustls::TlsAcceptor;
 
-use super::handler::{DNSAuthority, DNSHandler};
+use super::handler::DNSHandler;
+use super::proxy::DNSProxy;
+use super::tls::{DNSTLSCertificate, DNS_TLS};
 use crate::APP_CONF;
 
-lazy_static! {
-    static ref NAME_SOA_MASTER: Name =
-        Name::parse(&APP_CONF.dns.soa_master, Some(&Name::new())).expect("invalid soa master");
-    static ref NAME_SOA_RESPONSIBLE: Name =
-        Name::parse(&APP_CONF.dns.soa_responsible, Some(&Name::new()))
-            .expect("invalid soa responsible");
-}
-
-static SERIAL_DEFAULT: u32 = 1;
+static UDP_BUFFER_SIZE: usize = 4096;
+static TLS_ALPN_DOT: &[u8] = b"dot";
+static TLS_ALPN_DOQ: &[u8] = b"doq";
+static QUIC_ERROR_PROTOCOL_ERROR: u32 = 0x2;
 
 pub struct DNSListenBuilder;
 pub struct DNSListen;
@@ -39,21 +39,15 @@ impl DNSListen {
     #[tokio::main]
     pub async fn run(&self) {
         // Prepare the DNS handler
-        // Notice: upsert all available authorities from the configuration.
-        let mut handler: DNSHandler = DNSHandler::new();
-
-        for (zone_name, _) in &APP_CONF.dns.zone {
-            match Self::zone_authority(&zone_name) {
-                Ok((name, authority)) => handler.add_authority(LowerName::new(&name), authority),
-                Err(_) => error!("could not load zone {}", zone_name),
-            }
-        }
+        let handler = Arc::new(DNSHandler::new());
 
         // Run the DNS server
-        let mut server = ServerFuture::new(handler);
+        // Notice: sockets are served from our own loops rather than from the Hickory server, \
+        //   as dynamic updates need access to raw request messages (for TSIG verification).
+        let mut listeners = JoinSet::new();
 
         // Register sockets & listeners
-        for inet in &APP_CONF.dns.inets {
+        for inet in &APP_CONF.load().dns.inets {
             let udp_socket = UdpSocket::bind(inet)
                 .await
                 .expect(&format!("udp bind failed: {}", inet));
@@ -62,73 +56,318 @@ impl DNSListen {
                 .expect(&format!("tcp bind failed: {}", inet));
 
             info!("will listen for udp on {:?}", udp_socket);
-            server.register_socket(udp_socket);
+            listeners.spawn(Self::listen_udp(handler.clone(), udp_socket));
 
             info!("will listen for tcp on {:?}", tcp_listener);
-            server.register_listener(tcp_listener, Duration::from_secs(APP_CONF.dns.tcp_timeout));
+            listeners.spawn(Self::listen_tcp(handler.clone(), tcp_listener));
+        }
+
+        // Register TLS listeners? (DNS-over-TLS)
+        if !APP_CONF.load().dns.tls.inets.is_empty() {
+            let tls_acceptor = TlsAcceptor::from(
+                DNSTLSCertificate::server_config(&[TLS_ALPN_DOT]).expect("invalid tls certificate"),
+            );
+
+            for inet in &APP_CONF.load().dns.tls.inets {
+                let tls_listener = TcpListener::bind(inet)
+                    .await
+                    .unwrap_or_else(|_| panic!("tls bind failed: {}", inet));
+
+                info!("will listen for tls on {:?}", tls_listener);
+                listeners.spawn(Self::listen_tls(
+                    handler.clone(),
+                    tls_listener,
+                    tls_acceptor.clone(),
+                ));
+            }
+        }
+
+        // Register QUIC endpoints? (DNS-over-QUIC)
+        if !APP_CONF.load().dns.quic.inets.is_empty() {
+            let quic_config = Self::quic_config();
+
+            for inet in &APP_CONF.load().dns.quic.inets {
+                let quic_endpoint = Endpoint::server(quic_config.clone(), *inet)
+                    .unwrap_or_else(|_| panic!("quic bind failed: {}", inet));
+
+                info!("will listen for quic on {}", inet);
+                listeners.spawn(Self::listen_quic(handler.clone(), quic_endpoint));
+            }
+        }
+
+        // Maintain TLS certificate? (shared by DNS-over-TLS, DNS-over-QUIC and DNS-over-HTTPS \
+        //   listeners)
+        if !APP_CONF.load().dns.tls.inets.is_empty()
+            || !APP_CONF.load().dns.quic.inets.is_empty()
+            || (APP_CONF.load().dns.https.tls && !APP_CONF.load().dns.https.inets.is_empty())
+        {
+            listeners.spawn(async { DNS_TLS.maintain().await });
         }
 
         // Listen for connections
         info!("listening for dns connections");
 
-        if let Err(err) = server.block_until_done().await {
-            error!("failed to listen on dns: {}", err);
+        while let Some(result) = listeners.join_next().await {
+            if let Err(err) = result {
+                error!("failed to listen on dns: {}", err);
+            }
         }
     }
 
-    fn zone_authority(zone_name: &str) -> Result<(Name, DNSAuthority), ()> {
-        if let Ok(name) = Name::parse(zone_name, Some(&Name::new())) {
-            let mut records = BTreeMap::new();
-
-            // Insert base SOA records
-            let soa_records = RecordSet::from(Record::from_rdata(
-                name.to_owned(),
-                APP_CONF.dns.record_ttl,
-                RData::SOA(HickoryRData::SOA::new(
-                    NAME_SOA_MASTER.to_owned(),
-                    NAME_SOA_RESPONSIBLE.to_owned(),
-                    SERIAL_DEFAULT,
-                    APP_CONF.dns.soa_refresh,
-                    APP_CONF.dns.soa_retry,
-                    APP_CONF.dns.soa_expire,
-                    APP_CONF.dns.soa_ttl,
-                )),
-            ));
-
-            records.insert(
-                RrKey::new(LowerName::new(&name), RecordType::SOA),
-                soa_records,
-            );
+    async fn listen_udp(handler: Arc<DNSHandler>, socket: UdpSocket) {
+        let socket = Arc::new(socket);
+        let mut buffer = [0; UDP_BUFFER_SIZE];
+
+        loop {
+            match socket.recv_from(&mut buffer).await {
+                Ok((size, peer)) => {
+                    // Strip PROXY header? (if sent from a trusted load balancer)
+                    let (src, message) = match DNSProxy::read_datagram(&buffer[..size], peer) {
+                        Ok((src, message)) => (src, message.to_vec()),
+                        Err(_) => {
+                            debug!("dropped udp datagram with invalid proxy header: {}", peer);
+
+                            continue;
+                        }
+                    };
 
-            // Insert base NS records
-            let mut ns_records = RecordSet::new(&name, RecordType::NS, SERIAL_DEFAULT);
-
-            for nameserver in &APP_CONF.dns.nameservers {
-                ns_records.insert(
-                    Record::from_rdata(
-                        name.to_owned(),
-                        APP_CONF.dns.record_ttl,
-                        RData::NS(HickoryRData::NS(
-                            Name::parse(nameserver, Some(&Name::new()))
-                                .expect("invalid nameserver"),
-                        )),
-                    ),
-                    SERIAL_DEFAULT,
-                );
+                    let (handler, socket) = (handler.clone(), socket.clone());
+
+                    // Notice: responses are sent back to the peer, which may be a load balancer.
+                    tokio::spawn(async move {
+                        for response in handler.handle_raw(&message, src, Protocol::Udp).await {
+                            if let Err(err) = socket.send_to(&response, peer).await {
+                                debug!("failed sending udp response to: {}: {}", peer, err);
+                            }
+                        }
+                    });
+                }
+                Err(err) => warn!("failed receiving on udp socket: {}", err),
             }
+        }
+    }
 
-            records.insert(
-                RrKey::new(LowerName::new(&name), RecordType::NS),
-                ns_records,
-            );
+    async fn listen_tcp(handler: Arc<DNSHandler>, listener: TcpListener) {
+        loop {
+            match listener.accept().await {
+                Ok((mut stream, peer)) => {
+                    let handler = handler.clone();
+
+                    tokio::spawn(async move {
+                        let src = match Self::read_proxy_header(&mut stream, peer).await {
+                            Some(src) => src,
+                            None => return,
+                        };
+
+                        if let Err(err) =
+                            Self::serve_stream(handler, stream, src, Protocol::Tcp).await
+                        {
+                            debug!("closed tcp connection from: {}: {}", src, err);
+                        }
+                    });
+                }
+                Err(err) => warn!("failed accepting on tcp listener: {}", err),
+            }
+        }
+    }
+
+    async fn listen_tls(handler: Arc<DNSHandler>, listener: TcpListener, acceptor: TlsAcceptor) {
+        loop {
+            match listener.accept().await {
+                Ok((mut stream, peer)) => {
+                    let (handler, acceptor) = (handler.clone(), acceptor.clone());
+
+                    tokio::spawn(async move {
+                        let tcp_timeout = Duration::from_secs(APP_CONF.load().dns.tcp_timeout);
+
+                        // Notice: the PROXY header is sent before the TLS handshake.
+                        let src = match Self::read_proxy_header(&mut stream, peer).await {
+                            Some(src) => src,
+                            None => return,
+                        };
+
+                        // Perform TLS handshake, then serve messages as if it was plain TCP
+                        let stream = match timeout(tcp_timeout, acceptor.accept(stream)).await {
+                            Ok(Ok(stream)) => stream,
+                            Ok(Err(err)) => {
+                                debug!("failed tls handshake from: {}: {}", src, err);
+
+                                return;
+                            }
+                            Err(_) => {
+                                debug!("timed out tls handshake from: {}", src);
+
+                                return;
+                            }
+                        };
+
+                        if let Err(err) =
+                            Self::serve_stream(handler, stream, src, Protocol::Tls).await
+                        {
+                            debug!("closed tls connection from: {}: {}", src, err);
+                        }
+                    });
+                }
+                Err(err) => warn!("failed accepting on tls listener: {}", err),
+            }
+        }
+    }
+
+    async fn listen_quic(handler: Arc<DNSHandler>, endpoint: Endpoint) {
+        while let Some(connecting) = endpoint.accept().await {
+            let handler = handler.clone();
+
+            tokio::spawn(async move {
+                let src = connecting.remote_address();
 
-            // Build authority instance
-            let authority =
-                DNSAuthority::new(name.clone(), records, ZoneType::Primary, false).or(Err(()))?;
+                let connection = match connecting.await {
+                    Ok(connection) => connection,
+                    Err(err) => {
+                        debug!("failed quic handshake from: {}: {}", src, err);
 
-            Ok((name, authority))
-        } else {
-            Err(())
+                        return;
+                    }
+                };
+
+                // Serve all streams opened over this connection (each stream carries one query)
+                loop {
+                    match connection.accept_bi().await {
+                        Ok((send, recv)) => {
+                            let (handler, connection) = (handler.clone(), connection.clone());
+
+                            tokio::spawn(async move {
+                                if let Err(err) =
+                                    Self::serve_quic_stream(handler, &connection, send, recv).await
+                                {
+                                    debug!("closed quic stream from: {}: {}", src, err);
+                                }
+                            });
+                        }
+                        Err(err) => {
+                            debug!("closed quic connection from: {}: {}", src, err);
+
+                            return;
+                        }
+                    }
+                }
+            });
+        }
+    }
+
+    async fn serve_quic_stream(
+        handler: Arc<DNSHandler>,
+        connection: &Connection,
+        mut send: SendStream,
+        mut recv: RecvStream,
+    ) -> Result<(), std::io::Error> {
+        let tcp_timeout = Duration::from_secs(APP_CONF.load().dns.tcp_timeout);
+
+        // Read query message (prefixed with its size, as over TCP)
+        let mut size = [0; 2];
+
+        timeout(tcp_timeout, recv.read_exact(&mut size))
+            .await?
+            .map_err(std::io::Error::other)?;
+
+        let mut message = vec![0; u16::from_be_bytes(size) as usize];
+
+        timeout(tcp_timeout, recv.read_exact(&mut message))
+            .await?
+            .map_err(std::io::Error::other)?;
+
+        // Notice: as per RFC 9250, the message identifier must be zero, and any other value is \
+        //   a protocol error that closes the whole connection.
+        if message.len() < 2 || message[..2] != [0, 0] {
+            connection.close(VarInt::from_u32(QUIC_ERROR_PROTOCOL_ERROR), b"");
+
+            return Ok(());
+        }
+
+        let src = connection.remote_address();
+
+        for response in handler.handle_raw(&message, src, Protocol::Quic).await {
+            let mut frame = Vec::with_capacity(response.len() + 2);
+
+            frame.extend_from_slice(&(response.len() as u16).to_be_bytes());
+            frame.extend_from_slice(&response);
+
+            timeout(tcp_timeout, send.write_all(&frame))
+                .await?
+                .map_err(std::io::Error::other)?;
         }
+
+        // Close the stream, as it will not be used anymore
+        timeout(tcp_timeout, send.finish())
+            .await?
+            .map_err(std::io::Error::other)?;
+
+        Ok(())
+    }
+
+    async fn read_proxy_header(stream: &mut TcpStream, peer: SocketAddr) -> Option<SocketAddr> {
+        let tcp_timeout = Duration::from_secs(APP_CONF.load().dns.tcp_timeout);
+
+        match timeout(tcp_timeout, DNSProxy::read_stream(stream, peer)).await {
+            Ok(Ok(src)) => Some(src),
+            Ok(Err(err)) => {
+                debug!("failed reading proxy header from: {}: {}", peer, err);
+
+                None
+            }
+            Err(_) => {
+                debug!("timed out reading proxy header from: {}", peer);
+
+                None
+            }
+        }
+    }
+
+    async fn serve_stream<S: AsyncRead + AsyncWrite + Unpin>(
+        handler: Arc<DNSHandler>,
+        mut stream: S,
+        src: SocketAddr,
+        protocol: Protocol,
+    ) -> Result<(), std::io::Error> {
+        let tcp_timeout = Duration::from_secs(APP_CONF.load().dns.tcp_timeout);
+
+        // Serve all messages sent over this connection (each message is prefixed with its size)
+        loop {
+            let size = match timeout(tcp_timeout, stream.read_u16()).await {
+                Ok(Ok(size)) => size as usize,
+                Ok(Err(_)) | Err(_) => return Ok(()),
+            };
+
+            let mut message = vec![0; size];
+
+            timeout(tcp_timeout, stream.read_exact(&mut message)).await??;
+
+            for response in handler.handle_raw(&message, src, protocol).await {
+                let mut frame = Vec::with_capacity(response.len() + 2);
+
+                frame.extend_from_slice(&(response.len() as u16).to_be_bytes());
+                frame.extend_from_slice(&response);
+
+                timeout(tcp_timeout, stream.write_all(&frame)).await??;
+            }
+        }
+    }
+
+    fn quic_config() -> ServerConfig {
+        let mut quic_config = ServerConfig::with_crypto(
+            DNSTLSCertificate::server_config(&[TLS_ALPN_DOQ]).expect("invalid tls certificate"),
+        );
+
+        // Notice: idle connections are closed after the same delay as TCP connections.
+        let mut transport_config = TransportConfig::default();
+
+        transport_config.max_idle_timeout(
+            Duration::from_secs(APP_CONF.load().dns.tcp_timeout)
+                .try_into()
+                .ok(),
+        );
+
+        quic_config.transport_config(Arc::new(transport_config));
+
+        quic_config
     }
 }
@@ -14,10 +14,11 @@ use std::time::Duration;
 use std::{fmt, str};
 
 use super::code::CodeName;
+use super::ratelimit::RateLimitAction;
 use super::record::RecordType;
+use super::registry::DNS_REGISTRY;
 use super::zone::ZoneName;
 use crate::geo::country::CountryCode;
-use crate::APP_CONF;
 
 lazy_static! {
     pub static ref METRICS_STORE: MetricsStore = MetricsStoreBuilder::new();
@@ -40,6 +41,7 @@ pub struct DNSMetricsTick;
 pub type MetricsStoreQueryTypeType = HashMap<Option<RecordType>, MetricsStoreCountType>;
 pub type MetricsStoreQueryOriginType = HashMap<Option<CountryCode>, MetricsStoreCountType>;
 pub type MetricsStoreAnswerCodeType = HashMap<Option<CodeName>, MetricsStoreCountType>;
+pub type MetricsStoreAnswerRateLimitType = HashMap<Option<RateLimitAction>, MetricsStoreCountType>;
 
 struct MetricsStoreBuilder;
 struct MetricsStoreZoneBuilder;
@@ -53,18 +55,21 @@ pub struct MetricsStoreZone {
     pub query_type: [MetricsStoreQueryTypeType; METRICS_BACKLOG_MINUTES + 1],
     pub query_origin: [MetricsStoreQueryOriginType; METRICS_BACKLOG_MINUTES + 1],
     pub answer_code: [MetricsStoreAnswerCodeType; METRICS_BACKLOG_MINUTES + 1],
+    pub answer_rate_limit: [MetricsStoreAnswerRateLimitType; METRICS_BACKLOG_MINUTES + 1],
 }
 
 pub enum MetricsType {
     QueryType,
     QueryOrigin,
     AnswerCode,
+    AnswerRateLimit,
 }
 
 pub enum MetricsValue<'a> {
     QueryType(&'a Option<RecordType>),
     QueryOrigin(&'a Option<CountryCode>),
     AnswerCode(&'a Option<CodeName>),
+    AnswerRateLimit(&'a Option<RateLimitAction>),
 }
 
 impl MetricsStoreBuilder {
@@ -114,15 +119,17 @@ impl DNSMetricsTick {
         let mut zones_write = METRICS_STORE.zones.write().unwrap();
 
         for (_, zone_store) in zones_write.iter_mut() {
-            let (store_query_type, store_query_origin, store_answer_code) = (
+            let (store_query_type, store_query_origin, store_answer_code, store_answer_rate_limit) = (
                 &mut zone_store.query_type,
                 &mut zone_store.query_origin,
                 &mut zone_store.answer_code,
+                &mut zone_store.answer_rate_limit,
             );
 
             gen_metrics_tick_perform_item!(store_query_type, METRICS_BACKLOG_MINUTES);
             gen_metrics_tick_perform_item!(store_query_origin, METRICS_BACKLOG_MINUTES);
             gen_metrics_tick_perform_item!(store_answer_code, METRICS_BACKLOG_MINUTES);
+            gen_metrics_tick_perform_item!(store_answer_rate_limit, METRICS_BACKLOG_MINUTES);
         }
     }
 }
@@ -146,6 +153,9 @@ impl MetricsStore {
                     self.stack_query_origin(zone_store, origin_country)
                 }
                 MetricsValue::AnswerCode(code) => self.stack_answer_code(zone_store, code),
+                MetricsValue::AnswerRateLimit(action) => {
+                    self.stack_answer_rate_limit(zone_store, action)
+                }
             }
         }
     }
@@ -156,7 +166,7 @@ impl MetricsStore {
         metrics_type: MetricsType,
         metrics_timespan: MetricsTimespan,
     ) -> Option<HashMap<String, MetricsStoreCountType>> {
-        if APP_CONF.dns.zone_exists(zone_name.to_str()) == true {
+        if DNS_REGISTRY.zone_exists(zone_name.to_str()) {
             let aggregated_map: HashMap<String, MetricsStoreCountType>;
 
             let zone_read = METRICS_STORE.zones.read().unwrap();
@@ -174,6 +184,10 @@ impl MetricsStore {
                     MetricsType::AnswerCode => {
                         aggregated_map = self.aggregate_answer_code(zone_store, aggregate_limit);
                     }
+                    MetricsType::AnswerRateLimit => {
+                        aggregated_map =
+                            self.aggregate_answer_rate_limit(zone_store, aggregate_limit);
+                    }
                 }
             } else {
                 aggregated_map = HashMap::new();
@@ -219,6 +233,18 @@ impl MetricsStore {
         gen_metrics_stack_item!(answer_code_counters, code);
     }
 
+    fn stack_answer_rate_limit(
+        &self,
+        store: &mut MetricsStoreZone,
+        action: &Option<RateLimitAction>,
+    ) {
+        debug!("stacking answer rate limit metric for action: {:?}", action);
+
+        let answer_rate_limit_counters = &mut store.answer_rate_limit[0];
+
+        gen_metrics_stack_item!(answer_rate_limit_counters, action);
+    }
+
     fn aggregate_query_type(
         &self,
         store: &MetricsStoreZone,
@@ -248,6 +274,16 @@ impl MetricsStore {
 
         gen_metrics_aggregate_item!(store_target, aggregate_limit, METRICS_BACKLOG_MINUTES)
     }
+
+    fn aggregate_answer_rate_limit(
+        &self,
+        store: &MetricsStoreZone,
+        aggregate_limit: u8,
+    ) -> HashMap<String, MetricsStoreCountType> {
+        let store_target = &store.answer_rate_limit;
+
+        gen_metrics_aggregate_item!(store_target, aggregate_limit, METRICS_BACKLOG_MINUTES)
+    }
 }
 
 impl MetricsTimespan {
@@ -9,10 +9,20 @@ mod macros;
 
 mod code;
 mod handler;
+mod proxy;
+mod ratelimit;
+mod responder;
+mod tls;
+mod update;
 
+pub mod catalog;
 pub mod flatten;
 pub mod health;
+pub mod https;
 pub mod listen;
 pub mod metrics;
 pub mod record;
+pub mod registry;
+pub mod secondary;
 pub mod zone;
+pub mod zonefile;
@@ -0,0 +1,267 @@
+// Constellation
+//
+// Pluggable authoritative DNS server
+// Copyright: 2026, Valerian Saliou <valerian@valeriansaliou.name>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use std::io::{Error as IoError, ErrorKind as IoErrorKind};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::str::{self, FromStr};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::APP_CONF;
+
+static V1_SIGNATURE: &[u8] = b"PROXY ";
+static V1_SIZE_MAX: usize = 107;
+static V2_SIGNATURE: &[u8] = b"\r\n\r\n\0\r\nQUIT\n";
+static V2_HEADER_SIZE: usize = 16;
+
+pub struct DNSProxy;
+
+impl DNSProxy {
+    pub fn is_trusted(ip: &IpAddr) -> bool {
+        APP_CONF
+            .load()
+            .dns
+            .proxy_protocol
+            .trusted_sources
+            .iter()
+            .any(|network| network.contains(ip))
+    }
+
+    pub async fn read_stream<S: AsyncRead + Unpin>(
+        stream: &mut S,
+        peer: SocketAddr,
+    ) -> Result<SocketAddr, IoError> {
+        // Connections from untrusted sources never carry a PROXY header
+        if !Self::is_trusted(&peer.ip()) {
+            return Ok(peer);
+        }
+
+        // Notice: the first 8 bytes are enough to tell both PROXY protocol versions apart \
+        //   (the shortest possible header is 15 bytes long).
+        let mut header = vec![0; 8];
+
+        stream.read_exact(&mut header).await?;
+
+        if header.starts_with(V1_SIGNATURE) {
+            // Read version 1 header until its CRLF terminator
+            while !header.ends_with(b"\r\n") {
+                if header.len() >= V1_SIZE_MAX {
+                    return Err(Self::error("proxy header v1 is too long"));
+                }
+
+                header.push(stream.read_u8().await?);
+            }
+
+            Self::parse_v1(&header, peer)
+        } else if header[..] == V2_SIGNATURE[..8] {
+            // Read version 2 header, then its addresses block
+            header.resize(V2_HEADER_SIZE, 0);
+
+            stream.read_exact(&mut header[8..]).await?;
+
+            let mut block = vec![0; u16::from_be_bytes([header[14], header[15]]) as usize];
+
+            stream.read_exact(&mut block).await?;
+
+            Self::parse_v2(&header, &block, peer)
+        } else {
+            Err(Self::error("missing proxy header"))
+        }
+    }
+
+    pub fn read_datagram(datagram: &[u8], peer: SocketAddr) -> Result<(SocketAddr, &[u8]), ()> {
+        // Datagrams from untrusted sources never carry a PROXY header
+        if !Self::is_trusted(&peer.ip()) {
+            return Ok((peer, datagram));
+        }
+
+        // Notice: only version 2 headers are supported over UDP, as per the PROXY protocol \
+        //   specification.
+        if datagram.len() < V2_HEADER_SIZE || !datagram.starts_with(V2_SIGNATURE) {
+            return Err(());
+        }
+
+        let block_end = V2_HEADER_SIZE + u16::from_be_bytes([datagram[14], datagram[15]]) as usize;
+
+        if datagram.len() < block_end {
+            return Err(());
+        }
+
+        let src = Self::parse_v2(
+            &datagram[..V2_HEADER_SIZE],
+            &datagram[V2_HEADER_SIZE..block_end],
+            peer,
+        )
+        .or(Err(()))?;
+
+        Ok((src, &datagram[block_end..]))
+    }
+
+    fn parse_v1(header: &[u8], peer: SocketAddr) -> Result<SocketAddr, IoError> {
+        let header = str::from_utf8(&header[..header.len() - 2])
+            .or(Err(Self::error("proxy header v1 is not valid text")))?;
+
+        let parts: Vec<&str> = header.split(' ').collect();
+
+        match parts.as_slice() {
+            ["PROXY", "TCP4", source, _, port, _] | ["PROXY", "TCP6", source, _, port, _] => {
+                Ok(SocketAddr::new(
+                    IpAddr::from_str(source)
+                        .or(Err(Self::error("invalid proxy header v1 source")))?,
+                    port.parse()
+                        .or(Err(Self::error("invalid proxy header v1 port")))?,
+                ))
+            }
+            // Notice: unknown connections are sent by the proxy on its own behalf (eg. for \
+            //   health checks), thus the real peer address is used.
+            ["PROXY", "UNKNOWN", ..] => Ok(peer),
+            _ => Err(Self::error("invalid proxy header v1")),
+        }
+    }
+
+    fn parse_v2(header: &[u8], block: &[u8], peer: SocketAddr) -> Result<SocketAddr, IoError> {
+        if header[..12] != V2_SIGNATURE[..] || header[12] >> 4 != 0x2 {
+            return Err(Self::error("invalid proxy header v2"));
+        }
+
+        // Local command? (sent by the proxy on its own behalf, eg. for health checks)
+        match header[12] & 0x0f {
+            0x0 => return Ok(peer),
+            0x1 => {}
+            _ => return Err(Self::error("invalid proxy header v2 command")),
+        }
+
+        // Notice: unspecified families (eg. UNIX sockets) carry no usable address, thus the \
+        //   real peer address is used.
+        match header[13] >> 4 {
+            0x1 if block.len() >= 12 => Ok(SocketAddr::new(
+                IpAddr::V4(Ipv4Addr::new(block[0], block[1], block[2], block[3])),
+                u16::from_be_bytes([block[8], block[9]]),
+            )),
+            0x2 if block.len() >= 36 => {
+                let mut source = [0; 16];
+
+                source.copy_from_slice(&block[..16]);
+
+                Ok(SocketAddr::new(
+                    IpAddr::V6(Ipv6Addr::from(source)),
+                    u16::from_be_bytes([block[32], block[33]]),
+                ))
+            }
+            0x1 | 0x2 => Err(Self::error("truncated proxy header v2 addresses")),
+            _ => Ok(peer),
+        }
+    }
+
+    fn error(message: &str) -> IoError {
+        IoError::new(IoErrorKind::InvalidData, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer() -> SocketAddr {
+        "10.0.0.1:4000".parse().unwrap()
+    }
+
+    fn v2_header(command: u8, family: u8, block_size: u16) -> Vec<u8> {
+        let mut header = V2_SIGNATURE.to_vec();
+
+        header.push(0x20 | command);
+        header.push(family << 4 | 0x1);
+        header.extend_from_slice(&block_size.to_be_bytes());
+
+        header
+    }
+
+    #[test]
+    fn it_parses_v1_tcp4() {
+        assert_eq!(
+            DNSProxy::parse_v1(b"PROXY TCP4 192.0.2.1 198.51.100.1 56324 53\r\n", peer()).unwrap(),
+            "192.0.2.1:56324".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn it_parses_v1_tcp6() {
+        assert_eq!(
+            DNSProxy::parse_v1(b"PROXY TCP6 2001:db8::1 2001:db8::2 56324 53\r\n", peer()).unwrap(),
+            "[2001:db8::1]:56324".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn it_parses_v1_unknown() {
+        assert_eq!(
+            DNSProxy::parse_v1(b"PROXY UNKNOWN\r\n", peer()).unwrap(),
+            peer()
+        );
+    }
+
+    #[test]
+    fn it_fails_parsing_invalid_v1() {
+        assert!(
+            DNSProxy::parse_v1(b"PROXY TCP4 192.0.2.1 198.51.100.1 56324\r\n", peer()).is_err()
+        );
+        assert!(
+            DNSProxy::parse_v1(b"PROXY TCP4 invalid 198.51.100.1 56324 53\r\n", peer()).is_err()
+        );
+        assert!(
+            DNSProxy::parse_v1(b"PROXY TCP4 192.0.2.1 198.51.100.1 99999 53\r\n", peer()).is_err()
+        );
+        assert!(
+            DNSProxy::parse_v1(b"PROXY UDP4 192.0.2.1 198.51.100.1 56324 53\r\n", peer()).is_err()
+        );
+    }
+
+    #[test]
+    fn it_parses_v2_ipv4() {
+        let block = [192, 0, 2, 1, 198, 51, 100, 1, 0xdc, 0x04, 0x00, 0x35];
+
+        assert_eq!(
+            DNSProxy::parse_v2(&v2_header(0x1, 0x1, 12), &block, peer()).unwrap(),
+            "192.0.2.1:56324".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn it_parses_v2_ipv6() {
+        let mut block = vec![0; 36];
+
+        block[..16].copy_from_slice(&"2001:db8::1".parse::<Ipv6Addr>().unwrap().octets());
+        block[32..34].copy_from_slice(&56324_u16.to_be_bytes());
+
+        assert_eq!(
+            DNSProxy::parse_v2(&v2_header(0x1, 0x2, 36), &block, peer()).unwrap(),
+            "[2001:db8::1]:56324".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn it_parses_v2_local_and_unspecified() {
+        assert_eq!(
+            DNSProxy::parse_v2(&v2_header(0x0, 0x1, 0), &[], peer()).unwrap(),
+            peer()
+        );
+        assert_eq!(
+            DNSProxy::parse_v2(&v2_header(0x1, 0x0, 0), &[], peer()).unwrap(),
+            peer()
+        );
+    }
+
+    #[test]
+    fn it_fails_parsing_invalid_v2() {
+        let mut header = v2_header(0x1, 0x1, 12);
+
+        header[12] = 0x11;
+
+        assert!(DNSProxy::parse_v2(&header, &[0; 12], peer()).is_err());
+        assert!(DNSProxy::parse_v2(&v2_header(0x2, 0x1, 12), &[0; 12], peer()).is_err());
+        assert!(DNSProxy::parse_v2(&v2_header(0x1, 0x1, 8), &[0; 8], peer()).is_err());
+        assert!(DNSProxy::parse_v2(&v2_header(0x1, 0x2, 12), &[0; 12], peer()).is_err());
+    }
+}
@@ -0,0 +1,321 @@
+// Constellation
+//
+// Pluggable authoritative DNS server
+// Copyright: 2026, Valerian Saliou <valerian@valeriansaliou.name>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use hickory_proto::op::{Header, Message, ResponseCode};
+use hickory_proto::rr::{LowerName, RecordType as HickoryRecordType};
+use hickory_proto::serialize::binary::{BinDecodable, BinDecoder};
+use hickory_server::server::Request;
+use ipnet::IpNet;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::metrics::{MetricsValue, METRICS_STORE};
+use super::zone::ZoneName;
+use crate::APP_CONF;
+
+lazy_static! {
+    pub static ref DNS_RATE_LIMIT: DNSRateLimit = DNSRateLimitBuilder::new();
+}
+
+struct DNSRateLimitBuilder;
+
+pub struct DNSRateLimit {
+    buckets: Mutex<DNSRateLimitBuckets>,
+}
+
+struct DNSRateLimitBuckets {
+    entries: HashMap<DNSRateLimitKey, DNSRateLimitBucket>,
+    purged_at: Instant,
+}
+
+#[derive(Eq, PartialEq, Hash)]
+struct DNSRateLimitKey {
+    prefix: IpNet,
+    class: DNSRateLimitClass,
+    name: Option<LowerName>,
+    record_type: Option<HickoryRecordType>,
+}
+
+struct DNSRateLimitBucket {
+    balance: f64,
+    slipped: u32,
+    updated_at: Instant,
+}
+
+#[derive(Eq, PartialEq, Hash)]
+enum DNSRateLimitClass {
+    Answer,
+    NoData,
+    NXDomain,
+    Error,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum RateLimitAction {
+    Dropped,
+    Slipped,
+}
+
+impl DNSRateLimitBuilder {
+    fn new() -> DNSRateLimit {
+        DNSRateLimit {
+            buckets: Mutex::new(DNSRateLimitBuckets {
+                entries: HashMap::new(),
+                purged_at: Instant::now(),
+            }),
+        }
+    }
+}
+
+impl DNSRateLimit {
+    pub fn limit(
+        &self,
+        request: &Request,
+        response: Vec<u8>,
+        zone: Option<LowerName>,
+    ) -> Option<Vec<u8>> {
+        let source = request.src().ip();
+
+        // Rate limiting disabled, or client exempted?
+        if !APP_CONF.load().dns.rate_limit.enable
+            || APP_CONF
+                .load()
+                .dns
+                .rate_limit
+                .exempt_clients
+                .iter()
+                .any(|network| network.contains(&source))
+        {
+            return Some(response);
+        }
+
+        // Classify response (only its header needs to be decoded)
+        let header = match Header::read(&mut BinDecoder::new(&response)) {
+            Ok(header) => header,
+            Err(_) => return Some(response),
+        };
+
+        // Notice: positive answers are accounted per query name and type, while negative \
+        //   answers are accounted per zone, so that random subdomain floods share one bucket.
+        let (class, name, record_type, rate) = match header.response_code() {
+            ResponseCode::NoError if header.answer_count() > 0 => (
+                DNSRateLimitClass::Answer,
+                Some(request.query().name().to_owned()),
+                Some(request.query().query_type()),
+                APP_CONF.load().dns.rate_limit.responses_per_second,
+            ),
+            ResponseCode::NoError => (
+                DNSRateLimitClass::NoData,
+                zone.clone(),
+                None,
+                APP_CONF.load().dns.rate_limit.responses_per_second,
+            ),
+            ResponseCode::NXDomain => (
+                DNSRateLimitClass::NXDomain,
+                zone.clone(),
+                None,
+                APP_CONF.load().dns.rate_limit.nxdomains_per_second,
+            ),
+            _ => (
+                DNSRateLimitClass::Error,
+                None,
+                None,
+                APP_CONF.load().dns.rate_limit.errors_per_second,
+            ),
+        };
+
+        // Class is not rate limited? (a rate of zero means unlimited)
+        if rate == 0 {
+            return Some(response);
+        }
+
+        let key = DNSRateLimitKey {
+            prefix: Self::prefix(source),
+            class,
+            name,
+            record_type,
+        };
+
+        let action = self.account(key, rate);
+
+        // Stack rate limit action to metrics?
+        if let Some(ref action) = action {
+            if let Some(zone_name) = zone.as_ref().and_then(ZoneName::from_hickory) {
+                METRICS_STORE.stack(
+                    &zone_name,
+                    MetricsValue::AnswerRateLimit(&Some(action.to_owned())),
+                );
+            }
+        }
+
+        match action {
+            None => Some(response),
+            Some(RateLimitAction::Dropped) => {
+                debug!("rate limit dropped response to: {}", source);
+
+                None
+            }
+            Some(RateLimitAction::Slipped) => {
+                debug!("rate limit slipped response to: {}", source);
+
+                Self::truncated(request)
+            }
+        }
+    }
+
+    fn account(&self, key: DNSRateLimitKey, rate: u32) -> Option<RateLimitAction> {
+        let (now, rate, window) = (
+            Instant::now(),
+            rate as f64,
+            APP_CONF.load().dns.rate_limit.window.max(1),
+        );
+
+        let mut buckets = self.buckets.lock().unwrap();
+
+        // Purge idle buckets? (at most once per window)
+        // Notice: a bucket idle for a whole window has its debt fully paid back, thus it can \
+        //   be removed without any effect.
+        let window_duration = Duration::from_secs(window as u64);
+
+        if now.duration_since(buckets.purged_at) >= window_duration {
+            buckets
+                .entries
+                .retain(|_, bucket| now.duration_since(bucket.updated_at) < window_duration);
+
+            buckets.purged_at = now;
+        }
+
+        let bucket = buckets
+            .entries
+            .entry(key)
+            .or_insert_with(|| DNSRateLimitBucket {
+                balance: rate,
+                slipped: 0,
+                updated_at: now,
+            });
+
+        // Refill bucket with credits earned since last response, then spend one credit
+        // Notice: debt is capped to one window worth of credits.
+        let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+
+        bucket.balance =
+            ((bucket.balance + elapsed * rate).min(rate) - 1.0).max(-rate * window as f64);
+        bucket.updated_at = now;
+
+        if bucket.balance >= 0.0 {
+            return None;
+        }
+
+        // Notice: every n-th limited response is slipped (ie. sent as truncated), so that \
+        //   legitimate clients get a chance to retry over TCP.
+        Some(Self::slip_action(
+            bucket,
+            APP_CONF.load().dns.rate_limit.slip,
+        ))
+    }
+
+    fn slip_action(bucket: &mut DNSRateLimitBucket, slip: u32) -> RateLimitAction {
+        bucket.slipped = bucket.slipped.wrapping_add(1);
+
+        // Notice: 'is_multiple_of()' is not used there, as it requires a recent Rust version.
+        #[allow(clippy::manual_is_multiple_of)]
+        let is_slipped = slip > 0 && bucket.slipped % slip == 0;
+
+        if is_slipped {
+            RateLimitAction::Slipped
+        } else {
+            RateLimitAction::Dropped
+        }
+    }
+
+    fn prefix(source: IpAddr) -> IpNet {
+        let prefix_length = match source {
+            IpAddr::V4(_) => APP_CONF.load().dns.rate_limit.ipv4_prefix_length.min(32),
+            IpAddr::V6(_) => APP_CONF.load().dns.rate_limit.ipv6_prefix_length.min(128),
+        };
+
+        IpNet::new(source, prefix_length)
+            .map(|network| network.trunc())
+            .unwrap_or_else(|_| IpNet::from(source))
+    }
+
+    fn truncated(request: &Request) -> Option<Vec<u8>> {
+        let mut header = Header::response_from_request(request.header());
+
+        header.set_truncated(true);
+
+        let mut message = Message::new();
+
+        message
+            .set_header(header)
+            .add_query(request.query().original().to_owned());
+
+        message.to_vec().ok()
+    }
+}
+
+impl RateLimitAction {
+    pub fn to_str(&self) -> &'static str {
+        match *self {
+            RateLimitAction::Dropped => "Dropped",
+            RateLimitAction::Slipped => "Slipped",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bucket() -> DNSRateLimitBucket {
+        DNSRateLimitBucket {
+            balance: -1.0,
+            slipped: 0,
+            updated_at: Instant::now(),
+        }
+    }
+
+    fn actions(slip: u32, count: usize) -> Vec<RateLimitAction> {
+        let mut bucket = bucket();
+
+        (0..count)
+            .map(|_| DNSRateLimit::slip_action(&mut bucket, slip))
+            .collect()
+    }
+
+    #[test]
+    fn it_slips_every_nth_response() {
+        assert_eq!(
+            actions(2, 4),
+            vec![
+                RateLimitAction::Dropped,
+                RateLimitAction::Slipped,
+                RateLimitAction::Dropped,
+                RateLimitAction::Slipped,
+            ]
+        );
+        assert_eq!(
+            actions(3, 3),
+            vec![
+                RateLimitAction::Dropped,
+                RateLimitAction::Dropped,
+                RateLimitAction::Slipped,
+            ]
+        );
+    }
+
+    #[test]
+    fn it_slips_all_responses() {
+        assert_eq!(actions(1, 3), vec![RateLimitAction::Slipped; 3]);
+    }
+
+    #[test]
+    fn it_never_slips_responses() {
+        assert_eq!(actions(0, 3), vec![RateLimitAction::Dropped; 3]);
+    }
+}
@@ -5,7 +5,9 @@
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
 use hickory_proto::rr::rdata::{self as HickoryRData};
-use hickory_proto::rr::{LowerName as HickoryLowerName, RData, RecordType as HickoryRecordType};
+use hickory_proto::rr::{
+    LowerName as HickoryLowerName, Name as HickoryName, RData, RecordType as HickoryRecordType,
+};
 use hickory_proto::serialize::txt::RDataParser;
 use regex::Regex;
 use serde::de::{Error as DeserializeError, Unexpected, Visitor};
@@ -16,6 +18,7 @@ use std::ops::Deref;
 use std::{fmt, str};
 
 use crate::geo::country::CountryCode;
+use crate::geo::region::RegionCode;
 
 lazy_static! {
     static ref RECORD_NAME_REGEX: Regex = Regex::new(r"^(\*\.)?(([^\\/:@&\*]+)\.)?@$").unwrap();
@@ -25,6 +28,7 @@ static DATA_TXT_CHUNK_MAXIMUM: usize = 255;
 
 serde_string_impls!(RecordType, from_str);
 serde_string_impls!(RecordName, from_str);
+serde_string_impls!(RecordNamePattern, from_str);
 
 gen_record_type_impls!(
     A -> "a",
@@ -39,16 +43,19 @@ gen_record_type_impls!(
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub struct RecordName(String);
 
+#[derive(Clone, Debug)]
+pub struct RecordNamePattern(String, Regex);
+
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, Hash)]
 pub struct RecordValue(String);
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct RecordValues(Vec<RecordValue>);
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct RecordBlackhole(HashSet<CountryCode>);
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
 pub struct RecordRegions {
     pub nnam: Option<RecordValues>,
     pub snam: Option<RecordValues>,
@@ -123,7 +130,62 @@ impl RecordName {
     }
 }
 
+impl RecordNamePattern {
+    pub fn from_str(value: &str) -> Option<RecordNamePattern> {
+        let value = value.to_lowercase();
+
+        // Patterns are globs on record names, where '*' matches any sequence of characters \
+        //   (eg. '_acme-challenge.*@' matches all ACME challenge names in a zone)
+        if value.ends_with("@") {
+            let expression = value
+                .split("*")
+                .map(regex::escape)
+                .collect::<Vec<String>>()
+                .join(".*");
+
+            Regex::new(&format!("^{}$", expression))
+                .ok()
+                .map(|regex| RecordNamePattern(value, regex))
+        } else {
+            None
+        }
+    }
+
+    pub fn to_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn matches(&self, record_name: &RecordName) -> bool {
+        self.1.is_match(record_name.to_str())
+    }
+}
+
 impl RecordValue {
+    pub fn from_hickory(record_type: &RecordType, data: &RData) -> Option<RecordValue> {
+        // Notice: names are stored without their trailing dot, as they are when written from \
+        //   the HTTP API.
+        let value = match (record_type, data) {
+            (RecordType::A, RData::A(address)) => address.to_string(),
+            (RecordType::AAAA, RData::AAAA(address)) => address.to_string(),
+            (RecordType::CNAME, RData::CNAME(name)) => Self::name_to_string(name),
+            (RecordType::PTR, RData::PTR(name)) => Self::name_to_string(name),
+            (RecordType::MX, RData::MX(mx)) => format!(
+                "{} {}",
+                mx.preference(),
+                Self::name_to_string(mx.exchange())
+            ),
+            (RecordType::TXT, RData::TXT(txt)) => txt
+                .txt_data()
+                .iter()
+                .map(|chunk| String::from_utf8_lossy(chunk))
+                .collect(),
+            (RecordType::CAA, RData::CAA(_)) => data.to_string(),
+            _ => return None,
+        };
+
+        Some(RecordValue(value))
+    }
+
     pub fn to_hickory(&self, record_type: &RecordType) -> Result<RData, ()> {
         let hickory_record_type = record_type.to_hickory()?;
 
@@ -162,9 +224,73 @@ impl RecordValue {
         }
     }
 
+    pub fn to_zone_file(&self, record_type: &RecordType) -> String {
+        // Notice: names are stored without their trailing dot, though they are absolute names.
+        match record_type {
+            RecordType::A | RecordType::AAAA | RecordType::CAA => self.to_string(),
+            RecordType::CNAME | RecordType::PTR => Self::name_to_zone_file(self),
+            RecordType::MX => match self.split_once(' ') {
+                Some((preference, exchange)) => {
+                    format!("{} {}", preference, Self::name_to_zone_file(exchange))
+                }
+                None => self.to_string(),
+            },
+            RecordType::TXT => {
+                // Notice: chunks are split on character boundaries, so that UTF-8 text is \
+                //   kept as-is, while control characters are escaped, as per RFC 1035.
+                let (mut chunks, mut chunk, mut chunk_len) = (Vec::new(), String::new(), 0);
+
+                for character in self.chars() {
+                    if chunk_len + character.len_utf8() > DATA_TXT_CHUNK_MAXIMUM {
+                        chunks.push(chunk);
+
+                        chunk = String::new();
+                        chunk_len = 0;
+                    }
+
+                    match character {
+                        '"' | '\\' => chunk.push_str(&format!("\\{}", character)),
+                        _ if character.is_ascii_control() => {
+                            chunk.push_str(&format!("\\{:03}", character as u32))
+                        }
+                        _ => chunk.push(character),
+                    }
+
+                    chunk_len += character.len_utf8();
+                }
+
+                chunks.push(chunk);
+
+                chunks
+                    .iter()
+                    .map(|chunk| format!("\"{}\"", chunk))
+                    .collect::<Vec<String>>()
+                    .join(" ")
+            }
+        }
+    }
+
     pub fn to_str(&self) -> &str {
         &self.0
     }
+
+    fn name_to_zone_file(name: &str) -> String {
+        if name.ends_with(".") {
+            name.to_string()
+        } else {
+            format!("{}.", name)
+        }
+    }
+
+    fn name_to_string(name: &HickoryName) -> String {
+        let mut name_string = name.to_string();
+
+        if name_string.ends_with(".") && name_string.len() > 1 {
+            name_string.pop();
+        }
+
+        name_string
+    }
 }
 
 impl RecordBlackhole {
@@ -185,6 +311,51 @@ impl RecordValues {
     pub fn from_list(values: Vec<RecordValue>) -> RecordValues {
         RecordValues(values)
     }
+
+    pub fn add(&mut self, values: &RecordValues) {
+        for value in values.iter() {
+            if !self.0.contains(value) {
+                self.0.push(value.to_owned());
+            }
+        }
+    }
+
+    pub fn remove(&mut self, values: &RecordValues) {
+        self.0.retain(|value| !values.contains(value));
+    }
+}
+
+impl RecordRegions {
+    pub fn has_items(&self) -> bool {
+        [
+            &self.nnam, &self.snam, &self.nsam, &self.ssam, &self.weu, &self.ceu, &self.eeu,
+            &self.ru, &self.me, &self.naf, &self.maf, &self.saf, &self.seas, &self.neas, &self.oc,
+            &self._in,
+        ]
+        .iter()
+        .any(|values| values.is_some())
+    }
+
+    pub fn region_mut(&mut self, region: &RegionCode) -> &mut Option<RecordValues> {
+        match region {
+            RegionCode::NNAM => &mut self.nnam,
+            RegionCode::SNAM => &mut self.snam,
+            RegionCode::NSAM => &mut self.nsam,
+            RegionCode::SSAM => &mut self.ssam,
+            RegionCode::WEU => &mut self.weu,
+            RegionCode::CEU => &mut self.ceu,
+            RegionCode::EEU => &mut self.eeu,
+            RegionCode::RU => &mut self.ru,
+            RegionCode::ME => &mut self.me,
+            RegionCode::NAF => &mut self.naf,
+            RegionCode::MAF => &mut self.maf,
+            RegionCode::SAF => &mut self.saf,
+            RegionCode::IN => &mut self._in,
+            RegionCode::SEAS => &mut self.seas,
+            RegionCode::NEAS => &mut self.neas,
+            RegionCode::OC => &mut self.oc,
+        }
+    }
 }
 
 impl RecordValue {
@@ -210,3 +381,87 @@ impl Deref for RecordValue {
         &self.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(value: &str) -> RecordName {
+        RecordName::from_str(value).unwrap()
+    }
+
+    #[test]
+    fn it_parses_name_pattern() {
+        assert_eq!(
+            RecordNamePattern::from_str("_ACME-Challenge.*@")
+                .unwrap()
+                .to_str(),
+            "_acme-challenge.*@"
+        );
+        assert!(RecordNamePattern::from_str("www.@").is_some());
+        assert!(RecordNamePattern::from_str("*@").is_some());
+        assert!(RecordNamePattern::from_str("www.example.com").is_none());
+        assert!(RecordNamePattern::from_str("").is_none());
+    }
+
+    #[test]
+    fn it_matches_exact_name_pattern() {
+        let pattern = RecordNamePattern::from_str("www.@").unwrap();
+
+        assert!(pattern.matches(&name("www.@")));
+        assert!(pattern.matches(&name("WWW.@")));
+        assert!(!pattern.matches(&name("wwwx.@")));
+        assert!(!pattern.matches(&name("sub.www.@")));
+    }
+
+    #[test]
+    fn it_matches_wildcard_name_pattern() {
+        let pattern = RecordNamePattern::from_str("_acme-challenge.*@").unwrap();
+
+        assert!(pattern.matches(&name("_acme-challenge.@")));
+        assert!(pattern.matches(&name("_acme-challenge.www.@")));
+        assert!(pattern.matches(&name("_acme-challenge.a.b.@")));
+        assert!(!pattern.matches(&name("www.@")));
+        assert!(!pattern.matches(&name("www._acme-challenge.@")));
+    }
+
+    #[test]
+    fn it_escapes_name_pattern() {
+        let pattern = RecordNamePattern::from_str("a.b.@").unwrap();
+
+        assert!(pattern.matches(&name("a.b.@")));
+        assert!(!pattern.matches(&name("axb.@")));
+    }
+
+    #[test]
+    fn it_formats_txt_zone_file_value() {
+        assert_eq!(
+            RecordValue::from_string("v=spf1 -all".to_string()).to_zone_file(&RecordType::TXT),
+            "\"v=spf1 -all\""
+        );
+        assert_eq!(
+            RecordValue::from_string("a \"b\" \\c\td".to_string()).to_zone_file(&RecordType::TXT),
+            "\"a \\\"b\\\" \\\\c\\009d\""
+        );
+    }
+
+    #[test]
+    fn it_chunks_txt_zone_file_value() {
+        let value = RecordValue::from_string("a".repeat(300));
+
+        assert_eq!(
+            value.to_zone_file(&RecordType::TXT),
+            format!("\"{}\" \"{}\"", "a".repeat(255), "a".repeat(45))
+        );
+    }
+
+    #[test]
+    fn it_chunks_txt_zone_file_value_on_character_boundaries() {
+        let value = RecordValue::from_string(format!("{}éé", "a".repeat(254)));
+
+        assert_eq!(
+            value.to_zone_file(&RecordType::TXT),
+            format!("\"{}\" \"éé\"", "a".repeat(254))
+        );
+    }
+}
@@ -0,0 +1,299 @@
+// Constellation
+//
+// Pluggable authoritative DNS server
+// Copyright: 2026, Valerian Saliou <valerian@valeriansaliou.name>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use hickory_proto::rr::rdata as HickoryRData;
+use hickory_proto::rr::record_data::RData;
+use hickory_proto::rr::{LowerName, Name, Record, RecordSet, RecordType, RrKey};
+use hickory_server::authority::ZoneType;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::time;
+
+use super::catalog::DNS_CATALOG;
+use super::handler::DNSAuthority;
+use super::zone::ZoneName;
+use crate::config::config::ConfigDNSZoneMode;
+use crate::store::store::StoreError;
+use crate::{APP_CONF, APP_STORE};
+
+lazy_static! {
+    pub static ref DNS_REGISTRY: DNSRegistry = DNSRegistryBuilder::new();
+}
+
+static SERIAL_DEFAULT: u32 = 1;
+
+struct DNSRegistryBuilder;
+
+pub struct DNSRegistry {
+    authorities: RwLock<HashMap<LowerName, Arc<DNSAuthority>>>,
+    stored: RwLock<BTreeSet<String>>,
+}
+
+pub struct DNSRegistrySyncBuilder;
+pub struct DNSRegistrySync;
+
+impl DNSRegistryBuilder {
+    fn new() -> DNSRegistry {
+        DNSRegistry {
+            authorities: RwLock::new(DNSRegistry::config_authorities()),
+            stored: RwLock::new(BTreeSet::new()),
+        }
+    }
+}
+
+impl DNSRegistrySyncBuilder {
+    pub fn new() -> DNSRegistrySync {
+        DNSRegistrySync {}
+    }
+}
+
+impl DNSRegistrySync {
+    #[tokio::main]
+    pub async fn run(&self) {
+        info!("zone registry sync is now active");
+
+        // Notice: zones created or deleted from another node are picked up on next sync.
+        let mut interval =
+            time::interval(Duration::from_secs(APP_CONF.load().dns.zone_sync_interval));
+
+        loop {
+            interval.tick().await;
+
+            debug!("running a zone registry sync...");
+
+            match DNS_REGISTRY.sync().await {
+                Ok(_) => debug!("ran zone registry sync"),
+                Err(_) => error!("failed running zone registry sync"),
+            }
+        }
+    }
+}
+
+impl DNSRegistry {
+    pub fn zone_exists(&self, name: &str) -> bool {
+        // Notice: secondary zones are not reported as existing, as their records are transferred \
+        //   from their primary server, and thus cannot be managed there.
+        match APP_CONF.load().dns.zone.get(name) {
+            Some(zone) => zone.mode != ConfigDNSZoneMode::Secondary,
+            None => self.stored.read().unwrap().contains(name),
+        }
+    }
+
+    pub fn zone_stored(&self, name: &str) -> bool {
+        self.stored.read().unwrap().contains(name)
+    }
+
+    pub fn zone_names(&self) -> Vec<String> {
+        let mut zone_names: BTreeSet<String> = APP_CONF.load().dns.zone.keys().cloned().collect();
+
+        zone_names.extend(self.stored.read().unwrap().iter().cloned());
+
+        zone_names.into_iter().collect()
+    }
+
+    pub fn get_authority(&self, name: &LowerName) -> Option<Arc<DNSAuthority>> {
+        self.authorities.read().unwrap().get(name).cloned()
+    }
+
+    pub fn find_authority(&self, name: &LowerName) -> Option<Arc<DNSAuthority>> {
+        let authorities = self.authorities.read().unwrap();
+
+        // Walk up the name, until the closest zone is found
+        let mut name = name.to_owned();
+
+        loop {
+            if let Some(authority) = authorities.get(&name) {
+                return Some(authority.clone());
+            }
+
+            if name.is_root() {
+                return None;
+            }
+
+            name = name.base_name();
+        }
+    }
+
+    pub async fn sync(&self) -> Result<(), StoreError> {
+        let stored = APP_STORE
+            .list_zones()
+            .await?
+            .into_iter()
+            .filter_map(|zone_name| ZoneName::from_str(&zone_name))
+            .map(|zone_name| zone_name.to_str().to_owned())
+            .filter(|zone_name| !APP_CONF.load().dns.zone_exists(zone_name))
+            .collect();
+
+        self.apply(stored);
+
+        Ok(())
+    }
+
+    pub fn insert(&self, zone_name: &ZoneName) {
+        let mut stored = self.stored.read().unwrap().clone();
+
+        stored.insert(zone_name.to_str().to_owned());
+
+        self.apply(stored);
+    }
+
+    pub fn remove(&self, zone_name: &ZoneName) {
+        let mut stored = self.stored.read().unwrap().clone();
+
+        stored.remove(zone_name.to_str());
+
+        self.apply(stored);
+    }
+
+    pub fn reload(&self) {
+        {
+            let mut stored = self.stored.write().unwrap();
+
+            // Notice: stored zones that got declared in configuration are now served from \
+            //   configuration, and will be picked up again on next sync if they get removed.
+            stored.retain(|zone_name| !APP_CONF.load().dns.zone_exists(zone_name));
+
+            // Rebuild all authorities (as global and per-zone settings might have changed)
+            let mut authorities = Self::config_authorities();
+
+            for zone_name in stored.iter() {
+                match Self::zone_authority(zone_name) {
+                    Ok((name, authority)) => {
+                        authorities.insert(LowerName::new(&name), Arc::new(authority));
+                    }
+                    Err(_) => error!("could not load stored zone {}", zone_name),
+                }
+            }
+
+            *self.authorities.write().unwrap() = authorities;
+        }
+
+        // Notice: this must be done after locks are released, as the catalog lists zones.
+        DNS_CATALOG.refresh();
+    }
+
+    fn apply(&self, stored: BTreeSet<String>) {
+        {
+            let mut current = self.stored.write().unwrap();
+
+            // Stored zones did not change? (nothing to apply)
+            if *current == stored {
+                return;
+            }
+
+            let mut authorities = self.authorities.write().unwrap();
+
+            // Remove deleted zones
+            for zone_name in current.difference(&stored) {
+                if let Ok(name) = Name::parse(zone_name, Some(&Name::new())) {
+                    info!("removed stored zone: {}", zone_name);
+
+                    authorities.remove(&LowerName::new(&name));
+                }
+            }
+
+            // Add created zones
+            for zone_name in stored.difference(&current) {
+                match Self::zone_authority(zone_name) {
+                    Ok((name, authority)) => {
+                        info!("added stored zone: {}", zone_name);
+
+                        authorities.insert(LowerName::new(&name), Arc::new(authority));
+                    }
+                    Err(_) => error!("could not load stored zone {}", zone_name),
+                }
+            }
+
+            *current = stored;
+        }
+
+        // Notice: this must be done after locks are released, as the catalog lists zones.
+        DNS_CATALOG.refresh();
+    }
+
+    fn config_authorities() -> HashMap<LowerName, Arc<DNSAuthority>> {
+        let mut authorities = HashMap::new();
+
+        // Notice: secondary zones are served from transferred data, thus they are skipped there.
+        for (zone_name, zone) in &APP_CONF.load().dns.zone {
+            if zone.mode == ConfigDNSZoneMode::Secondary {
+                continue;
+            }
+
+            match Self::zone_authority(zone_name) {
+                Ok((name, authority)) => {
+                    authorities.insert(LowerName::new(&name), Arc::new(authority));
+                }
+                Err(_) => error!("could not load zone {}", zone_name),
+            }
+        }
+
+        authorities
+    }
+
+    fn zone_authority(zone_name: &str) -> Result<(Name, DNSAuthority), ()> {
+        if let Ok(name) = Name::parse(zone_name, Some(&Name::new())) {
+            let mut records = BTreeMap::new();
+
+            // Notice: zone settings default to global settings, if not overridden in zone.
+            let conf = APP_CONF.load();
+            let record_ttl = conf.dns.zone_record_ttl(zone_name);
+
+            // Insert base SOA records
+            let soa_records = RecordSet::from(Record::from_rdata(
+                name.to_owned(),
+                record_ttl,
+                RData::SOA(HickoryRData::SOA::new(
+                    Name::parse(conf.dns.zone_soa_master(zone_name), Some(&Name::new()))
+                        .expect("invalid soa master"),
+                    Name::parse(conf.dns.zone_soa_responsible(zone_name), Some(&Name::new()))
+                        .expect("invalid soa responsible"),
+                    SERIAL_DEFAULT,
+                    conf.dns.zone_soa_refresh(zone_name),
+                    conf.dns.zone_soa_retry(zone_name),
+                    conf.dns.zone_soa_expire(zone_name),
+                    conf.dns.zone_soa_ttl(zone_name),
+                )),
+            ));
+
+            records.insert(
+                RrKey::new(LowerName::new(&name), RecordType::SOA),
+                soa_records,
+            );
+
+            // Insert base NS records
+            let mut ns_records = RecordSet::new(&name, RecordType::NS, SERIAL_DEFAULT);
+
+            for nameserver in conf.dns.zone_nameservers(zone_name) {
+                ns_records.insert(
+                    Record::from_rdata(
+                        name.to_owned(),
+                        record_ttl,
+                        RData::NS(HickoryRData::NS(
+                            Name::parse(nameserver, Some(&Name::new()))
+                                .expect("invalid nameserver"),
+                        )),
+                    ),
+                    SERIAL_DEFAULT,
+                );
+            }
+
+            records.insert(
+                RrKey::new(LowerName::new(&name), RecordType::NS),
+                ns_records,
+            );
+
+            // Build authority instance
+            let authority =
+                DNSAuthority::new(name.clone(), records, ZoneType::Primary, false).or(Err(()))?;
+
+            Ok((name, authority))
+        } else {
+            Err(())
+        }
+    }
+}
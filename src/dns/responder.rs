@@ -0,0 +1,74 @@
+// Constellation
+//
+// Pluggable authoritative DNS server
+// Copyright: 2026, Valerian Saliou <valerian@valeriansaliou.name>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use hickory_proto::rr::Record;
+use hickory_proto::serialize::binary::BinEncoder;
+use hickory_proto::udp::MAX_RECEIVE_BUFFER_SIZE;
+use hickory_server::authority::MessageResponse;
+use hickory_server::server::{Protocol, ResponseHandler, ResponseInfo};
+use std::io;
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone)]
+pub struct DNSResponder {
+    protocol: Protocol,
+    response: Arc<Mutex<Option<Vec<u8>>>>,
+}
+
+impl DNSResponder {
+    pub fn new(protocol: Protocol) -> Self {
+        DNSResponder {
+            protocol,
+            response: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn take(&self) -> Option<Vec<u8>> {
+        self.response.lock().unwrap().take()
+    }
+}
+
+#[async_trait::async_trait]
+impl ResponseHandler for DNSResponder {
+    async fn send_response<'a>(
+        &mut self,
+        response: MessageResponse<
+            '_,
+            'a,
+            impl Iterator<Item = &'a Record> + Send + 'a,
+            impl Iterator<Item = &'a Record> + Send + 'a,
+            impl Iterator<Item = &'a Record> + Send + 'a,
+            impl Iterator<Item = &'a Record> + Send + 'a,
+        >,
+    ) -> io::Result<ResponseInfo> {
+        // Acquire maximum response size (only UDP responses are size-limited)
+        let max_size = match self.protocol {
+            Protocol::Udp => response
+                .get_edns()
+                .as_ref()
+                .map(|edns| edns.max_payload())
+                .unwrap_or(MAX_RECEIVE_BUFFER_SIZE as u16),
+            _ => u16::MAX,
+        };
+
+        // Encode response message (it will be sent back by the listener)
+        let mut buffer = Vec::with_capacity(512);
+
+        let info = {
+            let mut encoder = BinEncoder::new(&mut buffer);
+
+            encoder.set_max_size(max_size);
+
+            response
+                .destructive_emit(&mut encoder)
+                .map_err(|err| io::Error::other(format!("error encoding message: {}", err)))?
+        };
+
+        *self.response.lock().unwrap() = Some(buffer);
+
+        Ok(info)
+    }
+}
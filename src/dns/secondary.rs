@@ -0,0 +1,710 @@
+// Constellation
+//
+// Pluggable authoritative DNS server
+// Copyright: 2026, Valerian Saliou <valerian@valeriansaliou.name>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use hickory_proto::op::{Header, Message, MessageType, OpCode, Query, ResponseCode};
+use hickory_proto::rr::{
+    DNSClass, LowerName, Name, RData, Record, RecordSet, RecordType as HickoryRecordType, RrKey,
+};
+use hickory_server::authority::ZoneType;
+use hickory_server::server::Request;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{Error as IoError, ErrorKind as IoErrorKind};
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::Notify;
+use tokio::task::JoinSet;
+use tokio::time::timeout;
+
+use super::handler::DNSAuthority;
+use crate::config::config::ConfigDNSZoneMode;
+use crate::APP_CONF;
+
+lazy_static! {
+    pub static ref DNS_SECONDARY: DNSSecondary = DNSSecondaryBuilder::new();
+}
+
+struct DNSSecondaryBuilder;
+
+pub struct DNSSecondary {
+    zones: RwLock<HashMap<LowerName, DNSSecondaryZone>>,
+    reloaded: Notify,
+}
+
+pub struct DNSSecondaryTransferBuilder;
+pub struct DNSSecondaryTransfer;
+
+struct DNSSecondaryZone {
+    origin: Name,
+    primary: SocketAddr,
+    notify: Arc<Notify>,
+    authority: Option<Arc<DNSAuthority>>,
+    records: Vec<Record>,
+    expires_at: Option<Instant>,
+}
+
+enum DNSSecondaryTransferResult {
+    Current,
+    Full(Vec<Record>),
+    Incremental(Vec<(Vec<Record>, Vec<Record>)>, Box<Record>),
+}
+
+const TRANSFER_MESSAGE_ID: u16 = 0;
+const TRANSFER_RETRY_INITIAL: Duration = Duration::from_secs(10);
+
+impl DNSSecondaryBuilder {
+    fn new() -> DNSSecondary {
+        // Register all secondary zones from configuration (they start empty, until transferred)
+        let zones = DNSSecondary::config_zones()
+            .into_iter()
+            .map(|(name, (origin, primary))| (name, DNSSecondaryZone::new(origin, primary)))
+            .collect();
+
+        DNSSecondary {
+            zones: RwLock::new(zones),
+            reloaded: Notify::new(),
+        }
+    }
+}
+
+impl DNSSecondaryTransferBuilder {
+    pub fn new() -> DNSSecondaryTransfer {
+        DNSSecondaryTransfer {}
+    }
+}
+
+impl DNSSecondary {
+    pub fn find_authority(
+        &self,
+        name: &LowerName,
+    ) -> Option<(LowerName, Option<Arc<DNSAuthority>>)> {
+        let zones = self.zones.read().unwrap();
+        let mut name = name.to_owned();
+
+        loop {
+            if let Some(zone) = zones.get(&name) {
+                // Notice: an expired zone must not be served anymore (as per RFC 1035)
+                let authority = match zone.expires_at {
+                    Some(expires_at) if expires_at > Instant::now() => zone.authority.clone(),
+                    _ => None,
+                };
+
+                return Some((name, authority));
+            }
+
+            if name.is_root() {
+                return None;
+            }
+
+            name = name.base_name();
+        }
+    }
+
+    pub fn handle_notify(&self, request: &Request) -> Option<Vec<u8>> {
+        let query = request.query();
+
+        info!(
+            "notify request with identifier: {} for zone: {}",
+            request.id(),
+            query.name()
+        );
+
+        // Only accept notifications for known secondary zones, that come from their primary
+        let code = match self.zones.read().unwrap().get(query.name()) {
+            Some(zone) if zone.primary.ip() == request.src().ip() => {
+                debug!("accepted notify for secondary zone: {}", zone.origin);
+
+                zone.notify.notify_one();
+
+                ResponseCode::NoError
+            }
+            Some(_) => {
+                warn!(
+                    "refused notify for zone: {} from non-primary: {}",
+                    query.name(),
+                    request.src().ip()
+                );
+
+                ResponseCode::Refused
+            }
+            None => ResponseCode::NotAuth,
+        };
+
+        let mut header = Header::response_from_request(request.header());
+
+        header.set_response_code(code);
+        header.set_authoritative(code == ResponseCode::NoError);
+
+        let mut message = Message::new();
+
+        message
+            .set_header(header)
+            .add_query(query.original().to_owned());
+
+        message.to_vec().ok()
+    }
+
+    pub fn reload(&self) {
+        {
+            let mut zones = self.zones.write().unwrap();
+            let mut secondaries = Self::config_zones();
+
+            // Remove zones that are not secondary anymore
+            // Notice: their transfer worker is woken up, so that it stops right away.
+            zones.retain(|name, zone| {
+                if secondaries.contains_key(name) {
+                    true
+                } else {
+                    info!("removed secondary zone: {}", zone.origin);
+
+                    zone.notify.notify_one();
+
+                    false
+                }
+            });
+
+            // Add new zones, and update primary of existing zones
+            for (name, (origin, primary)) in secondaries.drain() {
+                match zones.get_mut(&name) {
+                    Some(zone) => {
+                        if zone.primary != primary {
+                            info!(
+                                "changed primary of secondary zone: {} to: {}",
+                                zone.origin, primary
+                            );
+
+                            zone.primary = primary;
+                            zone.notify.notify_one();
+                        }
+                    }
+                    None => {
+                        info!("added secondary zone: {}", origin);
+
+                        zones.insert(name, DNSSecondaryZone::new(origin, primary));
+                    }
+                }
+            }
+        }
+
+        // Notify transfer thread, so that it starts workers for new zones
+        self.reloaded.notify_one();
+    }
+
+    fn config_zones() -> HashMap<LowerName, (Name, SocketAddr)> {
+        let mut zones = HashMap::new();
+
+        // Notice: configuration is validated beforehand, thus invalid zones are skipped there.
+        for (zone_name, zone) in &APP_CONF.load().dns.zone {
+            if zone.mode == ConfigDNSZoneMode::Secondary {
+                if let (Ok(origin), Some(primary)) =
+                    (Name::parse(zone_name, Some(&Name::root())), zone.primary)
+                {
+                    zones.insert(LowerName::new(&origin), (origin, primary));
+                }
+            }
+        }
+
+        zones
+    }
+
+    fn zone_state(
+        &self,
+        name: &LowerName,
+    ) -> Option<(Name, SocketAddr, Arc<Notify>, Option<Record>)> {
+        self.zones.read().unwrap().get(name).map(|zone| {
+            (
+                zone.origin.to_owned(),
+                zone.primary,
+                zone.notify.clone(),
+                zone.authority
+                    .as_ref()
+                    .and_then(|_| Self::find_soa(&zone.records)),
+            )
+        })
+    }
+
+    fn zone_load(&self, name: &LowerName, records: Vec<Record>) -> Result<(), ()> {
+        let mut zones = self.zones.write().unwrap();
+        let zone = zones.get_mut(name).ok_or(())?;
+
+        let soa = Self::find_soa(&records).ok_or(())?;
+        let serial = Self::soa_serial(&soa).ok_or(())?;
+
+        // Group transferred records into record sets, and build authority from them
+        let mut record_sets: BTreeMap<RrKey, RecordSet> = BTreeMap::new();
+
+        for record in &records {
+            record_sets
+                .entry(RrKey::new(
+                    LowerName::new(record.name()),
+                    record.record_type(),
+                ))
+                .or_insert_with(|| RecordSet::new(record.name(), record.record_type(), serial))
+                .insert(record.to_owned(), serial);
+        }
+
+        let authority = DNSAuthority::new(
+            zone.origin.to_owned(),
+            record_sets,
+            ZoneType::Secondary,
+            false,
+        )
+        .map_err(|err| error!("could not build secondary zone: {}: {}", zone.origin, err))?;
+
+        zone.authority = Some(Arc::new(authority));
+        zone.records = records;
+
+        self.zone_refresh(zone);
+
+        Ok(())
+    }
+
+    fn zone_touch(&self, name: &LowerName) {
+        if let Some(zone) = self.zones.write().unwrap().get_mut(name) {
+            self.zone_refresh(zone);
+        }
+    }
+
+    fn zone_refresh(&self, zone: &mut DNSSecondaryZone) {
+        // Push back expiration (the zone was confirmed as current by its primary)
+        if let Some(expire) = Self::find_soa(&zone.records)
+            .as_ref()
+            .and_then(|soa| Self::soa_timer(soa, |soa| soa.expire()))
+        {
+            zone.expires_at = Some(Instant::now() + expire);
+        }
+    }
+
+    fn zone_records(&self, name: &LowerName) -> Vec<Record> {
+        self.zones
+            .read()
+            .unwrap()
+            .get(name)
+            .map(|zone| zone.records.to_owned())
+            .unwrap_or_default()
+    }
+
+    fn find_soa(records: &[Record]) -> Option<Record> {
+        records
+            .iter()
+            .find(|record| record.record_type() == HickoryRecordType::SOA)
+            .cloned()
+    }
+
+    fn soa_serial(record: &Record) -> Option<u32> {
+        match record.data() {
+            Some(RData::SOA(soa)) => Some(soa.serial()),
+            _ => None,
+        }
+    }
+
+    fn soa_timer<F>(record: &Record, timer: F) -> Option<Duration>
+    where
+        F: Fn(&hickory_proto::rr::rdata::SOA) -> i32,
+    {
+        match record.data() {
+            Some(RData::SOA(soa)) => Some(Duration::from_secs(timer(soa).max(1) as u64)),
+            _ => None,
+        }
+    }
+}
+
+impl DNSSecondaryZone {
+    fn new(origin: Name, primary: SocketAddr) -> DNSSecondaryZone {
+        DNSSecondaryZone {
+            origin,
+            primary,
+            notify: Arc::new(Notify::new()),
+            authority: None,
+            records: Vec::new(),
+            expires_at: None,
+        }
+    }
+}
+
+impl DNSSecondaryTransfer {
+    #[tokio::main]
+    pub async fn run(&self) {
+        info!("secondary zones transfer is now active");
+
+        let mut workers = JoinSet::new();
+        let mut running = HashSet::new();
+
+        loop {
+            // Start workers for zones that are not being maintained yet
+            let names: Vec<LowerName> = DNS_SECONDARY
+                .zones
+                .read()
+                .unwrap()
+                .keys()
+                .filter(|name| !running.contains(*name))
+                .cloned()
+                .collect();
+
+            for name in names {
+                running.insert(name.to_owned());
+
+                workers.spawn(Self::maintain(name));
+            }
+
+            // Wait for a worker to stop (ie. its zone was removed), or a configuration reload
+            tokio::select! {
+                Some(result) = workers.join_next(), if !workers.is_empty() => match result {
+                    Ok(name) => {
+                        running.remove(&name);
+                    }
+                    Err(err) => error!("secondary zone transfer worker failed: {}", err),
+                },
+                _ = DNS_SECONDARY.reloaded.notified() => {}
+            }
+        }
+    }
+
+    async fn maintain(name: LowerName) -> LowerName {
+        loop {
+            // Acquire current zone state (stop if zone was removed)
+            let (origin, primary, notify, soa) = match DNS_SECONDARY.zone_state(&name) {
+                Some(state) => state,
+                None => return name,
+            };
+
+            // Refresh zone from primary, and schedule next refresh depending on outcome
+            let wait = match Self::refresh(&name, &origin, primary, soa.as_ref()).await {
+                Ok(_) => DNS_SECONDARY
+                    .zone_state(&name)
+                    .and_then(|(_, _, _, soa)| soa)
+                    .and_then(|soa| DNSSecondary::soa_timer(&soa, |soa| soa.refresh())),
+                Err(err) => {
+                    warn!(
+                        "failed refreshing secondary zone: {} from primary: {}: {}",
+                        origin, primary, err
+                    );
+
+                    soa.as_ref()
+                        .and_then(|soa| DNSSecondary::soa_timer(soa, |soa| soa.retry()))
+                        .or(Some(TRANSFER_RETRY_INITIAL))
+                }
+            }
+            .unwrap_or(TRANSFER_RETRY_INITIAL);
+
+            debug!("next refresh of secondary zone: {} in {:?}", origin, wait);
+
+            // Wait until next refresh, or until a NOTIFY is received from primary
+            let _ = timeout(wait, notify.notified()).await;
+        }
+    }
+
+    async fn refresh(
+        name: &LowerName,
+        origin: &Name,
+        primary: SocketAddr,
+        soa: Option<&Record>,
+    ) -> Result<(), IoError> {
+        let mut stream = Self::connect(primary).await?;
+
+        // Check primary serial first? (only if zone was already loaded)
+        if let Some(soa) = soa {
+            let serial = DNSSecondary::soa_serial(soa).unwrap_or(0);
+            let primary_serial = Self::query_serial(&mut stream, origin).await?;
+
+            if !Self::serial_newer(primary_serial, serial) {
+                debug!(
+                    "secondary zone: {} is current at serial: {}",
+                    origin, serial
+                );
+
+                DNS_SECONDARY.zone_touch(name);
+
+                return Ok(());
+            }
+
+            info!(
+                "secondary zone: {} is outdated at serial: {} (primary has serial: {})",
+                origin, serial, primary_serial
+            );
+        }
+
+        // Transfer zone (incrementally if zone was already loaded)
+        // Notice: primaries that do not support IXFR reply with an error, thus fallback to AXFR.
+        let result = match Self::transfer(&mut stream, origin, soa).await {
+            Ok(result) => result,
+            Err(err) if soa.is_some() => {
+                debug!(
+                    "incremental transfer of secondary zone: {} failed: {}, trying full transfer",
+                    origin, err
+                );
+
+                let mut stream = Self::connect(primary).await?;
+
+                Self::transfer(&mut stream, origin, None).await?
+            }
+            Err(err) => return Err(err),
+        };
+
+        let records = match result {
+            DNSSecondaryTransferResult::Current => {
+                DNS_SECONDARY.zone_touch(name);
+
+                return Ok(());
+            }
+            DNSSecondaryTransferResult::Full(records) => records,
+            DNSSecondaryTransferResult::Incremental(differences, soa) => {
+                let mut records = DNS_SECONDARY.zone_records(name);
+
+                for (deletions, additions) in differences {
+                    records.retain(|record| {
+                        !deletions
+                            .iter()
+                            .any(|deletion| Self::record_matches(record, deletion))
+                    });
+
+                    records.extend(additions);
+                }
+
+                // Notice: the SOA record is replaced with the one from the final serial
+                records.retain(|record| record.record_type() != HickoryRecordType::SOA);
+                records.insert(0, *soa);
+
+                records
+            }
+        };
+
+        let records_count = records.len();
+
+        DNS_SECONDARY.zone_load(name, records).or(Err(IoError::new(
+            IoErrorKind::InvalidData,
+            "transferred zone is invalid",
+        )))?;
+
+        info!(
+            "transferred secondary zone: {} from primary: {} ({} records)",
+            origin, primary, records_count
+        );
+
+        Ok(())
+    }
+
+    async fn connect(primary: SocketAddr) -> Result<TcpStream, IoError> {
+        timeout(
+            Duration::from_secs(APP_CONF.load().dns.tcp_timeout),
+            TcpStream::connect(primary),
+        )
+        .await?
+    }
+
+    async fn query_serial(stream: &mut TcpStream, origin: &Name) -> Result<u32, IoError> {
+        Self::send(stream, origin, HickoryRecordType::SOA, None).await?;
+
+        let message = Self::receive(stream).await?;
+
+        message
+            .answers()
+            .iter()
+            .find(|record| record.record_type() == HickoryRecordType::SOA)
+            .and_then(DNSSecondary::soa_serial)
+            .ok_or_else(|| IoError::new(IoErrorKind::InvalidData, "no soa in primary response"))
+    }
+
+    async fn transfer(
+        stream: &mut TcpStream,
+        origin: &Name,
+        soa: Option<&Record>,
+    ) -> Result<DNSSecondaryTransferResult, IoError> {
+        let serial = soa.and_then(DNSSecondary::soa_serial);
+
+        // Request an incremental transfer if a serial is known (primary may send a full one)
+        let query_type = if soa.is_some() {
+            HickoryRecordType::IXFR
+        } else {
+            HickoryRecordType::AXFR
+        };
+
+        Self::send(stream, origin, query_type, soa).await?;
+
+        // Receive all answer records (transfers can span over multiple messages)
+        let mut records: Vec<Record> = Vec::new();
+        let mut final_serial: Option<u32> = None;
+
+        loop {
+            let mut message = Self::receive(stream).await?;
+
+            records.extend(message.take_answers());
+
+            // Acquire final serial from first record (which must be a SOA)
+            if final_serial.is_none() {
+                final_serial = Some(
+                    records
+                        .first()
+                        .and_then(DNSSecondary::soa_serial)
+                        .ok_or_else(|| {
+                            IoError::new(IoErrorKind::InvalidData, "transfer must start with soa")
+                        })?,
+                );
+            }
+
+            let final_serial = final_serial.unwrap_or(0);
+
+            // Primary replied with a single SOA record? (zone is current)
+            if records.len() == 1
+                && query_type == HickoryRecordType::IXFR
+                && !Self::serial_newer(final_serial, serial.unwrap_or(0))
+            {
+                return Ok(DNSSecondaryTransferResult::Current);
+            }
+
+            // Transfer is complete once the final SOA is seen twice (thrice if incremental, \
+            //   as the final SOA also opens the last set of additions)
+            let is_incremental = records.len() > 1
+                && records[1].record_type() == HickoryRecordType::SOA
+                && DNSSecondary::soa_serial(&records[1]) != Some(final_serial);
+
+            let final_soa_count = records
+                .iter()
+                .filter(|record| DNSSecondary::soa_serial(record) == Some(final_serial))
+                .count();
+
+            if final_soa_count >= if is_incremental { 3 } else { 2 } {
+                return if is_incremental {
+                    Self::parse_incremental(records)
+                } else {
+                    // Notice: drop the final SOA, as it is the same as the opening one
+                    records.pop();
+
+                    Ok(DNSSecondaryTransferResult::Full(records))
+                };
+            }
+        }
+    }
+
+    fn parse_incremental(mut records: Vec<Record>) -> Result<DNSSecondaryTransferResult, IoError> {
+        // Sequence is: SOA(final), [SOA(old), deletions.., SOA(new), additions..]*, SOA(final)
+        let final_soa = records.remove(0);
+
+        records.pop();
+
+        let mut differences: Vec<(Vec<Record>, Vec<Record>)> = Vec::new();
+        let mut is_adding = true;
+
+        for record in records {
+            if record.record_type() == HickoryRecordType::SOA {
+                if is_adding {
+                    differences.push((Vec::new(), Vec::new()));
+                }
+
+                is_adding = !is_adding;
+            } else {
+                let difference = differences.last_mut().ok_or_else(|| {
+                    IoError::new(IoErrorKind::InvalidData, "invalid incremental transfer")
+                })?;
+
+                if is_adding {
+                    difference.1.push(record);
+                } else {
+                    difference.0.push(record);
+                }
+            }
+        }
+
+        Ok(DNSSecondaryTransferResult::Incremental(
+            differences,
+            Box::new(final_soa),
+        ))
+    }
+
+    async fn send(
+        stream: &mut TcpStream,
+        origin: &Name,
+        query_type: HickoryRecordType,
+        soa: Option<&Record>,
+    ) -> Result<(), IoError> {
+        let mut query = Query::query(origin.to_owned(), query_type);
+
+        query.set_query_class(DNSClass::IN);
+
+        let mut message = Message::new();
+
+        message
+            .set_id(TRANSFER_MESSAGE_ID)
+            .set_message_type(MessageType::Query)
+            .set_op_code(OpCode::Query)
+            .add_query(query);
+
+        // Incremental transfers carry the current SOA in the authority section
+        if let Some(soa) = soa {
+            message.add_name_server(soa.to_owned());
+        }
+
+        let bytes = message
+            .to_vec()
+            .map_err(|err| IoError::new(IoErrorKind::InvalidData, err.to_string()))?;
+
+        let mut frame = Vec::with_capacity(bytes.len() + 2);
+
+        frame.extend_from_slice(&(bytes.len() as u16).to_be_bytes());
+        frame.extend_from_slice(&bytes);
+
+        timeout(
+            Duration::from_secs(APP_CONF.load().dns.tcp_timeout),
+            stream.write_all(&frame),
+        )
+        .await?
+    }
+
+    async fn receive(stream: &mut TcpStream) -> Result<Message, IoError> {
+        let tcp_timeout = Duration::from_secs(APP_CONF.load().dns.tcp_timeout);
+
+        let size = timeout(tcp_timeout, stream.read_u16()).await??;
+        let mut bytes = vec![0; size as usize];
+
+        timeout(tcp_timeout, stream.read_exact(&mut bytes)).await??;
+
+        let message = Message::from_vec(&bytes)
+            .map_err(|err| IoError::new(IoErrorKind::InvalidData, err.to_string()))?;
+
+        if message.response_code() != ResponseCode::NoError {
+            return Err(IoError::other(format!(
+                "primary replied with code: {}",
+                message.response_code()
+            )));
+        }
+
+        Ok(message)
+    }
+
+    fn record_matches(record: &Record, other: &Record) -> bool {
+        record.name() == other.name()
+            && record.record_type() == other.record_type()
+            && record.data() == other.data()
+    }
+
+    fn serial_newer(serial: u32, other: u32) -> bool {
+        // Compare serials using sequence space arithmetic (as per RFC 1982)
+        serial != other && serial.wrapping_sub(other) < (1 << 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_compares_serials() {
+        assert!(DNSSecondaryTransfer::serial_newer(2, 1));
+        assert!(!DNSSecondaryTransfer::serial_newer(1, 2));
+        assert!(!DNSSecondaryTransfer::serial_newer(1, 1));
+    }
+
+    #[test]
+    fn it_compares_wrapped_serials() {
+        assert!(DNSSecondaryTransfer::serial_newer(0, u32::MAX));
+        assert!(DNSSecondaryTransfer::serial_newer(5, u32::MAX - 5));
+        assert!(!DNSSecondaryTransfer::serial_newer(u32::MAX, 0));
+        assert!(DNSSecondaryTransfer::serial_newer((1 << 31) - 1, 0));
+        assert!(!DNSSecondaryTransfer::serial_newer(1 << 31, 0));
+    }
+}
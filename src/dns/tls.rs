@@ -0,0 +1,159 @@
+// Constellation
+//
+// Pluggable authoritative DNS server
+// Copyright: 2026, Valerian Saliou <valerian@valeriansaliou.name>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::{self, CertifiedKey};
+use rustls::{Certificate, PrivateKey, ServerConfig};
+use rustls_pemfile::Item;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+use tokio::time;
+
+use crate::APP_CONF;
+
+lazy_static! {
+    pub static ref DNS_TLS: Arc<DNSTLSCertificate> = Arc::new(DNSTLSCertificate {
+        current: RwLock::new(None),
+    });
+}
+
+pub struct DNSTLSCertificate {
+    current: RwLock<Option<DNSTLSCertificateLoaded>>,
+}
+
+struct DNSTLSCertificateLoaded {
+    key: Arc<CertifiedKey>,
+    modified_at: (SystemTime, SystemTime),
+}
+
+impl DNSTLSCertificate {
+    pub fn server_config(alpn: &[&[u8]]) -> Result<Arc<ServerConfig>, ()> {
+        // Load initial certificate (fails if no valid certificate is configured)
+        DNS_TLS.reload()?;
+
+        let mut config = ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_cert_resolver(DNS_TLS.clone());
+
+        config.alpn_protocols = alpn.iter().map(|protocol| protocol.to_vec()).collect();
+
+        Ok(Arc::new(config))
+    }
+
+    pub async fn maintain(&self) {
+        // Notice: certificates are reloaded whenever their files change on disk (eg. when they \
+        //   get renewed), without interrupting established connections.
+        let mut interval =
+            time::interval(Duration::from_secs(APP_CONF.load().dns.tls.reload_interval));
+
+        loop {
+            interval.tick().await;
+
+            let _ = self.reload();
+        }
+    }
+
+    fn reload(&self) -> Result<(), ()> {
+        let conf = APP_CONF.load();
+
+        let (certificate_path, key_path) =
+            match (&conf.dns.tls.certificate_path, &conf.dns.tls.key_path) {
+                (Some(certificate_path), Some(key_path)) => (certificate_path, key_path),
+                _ => {
+                    error!("tls certificate and key paths must be set in [dns.tls]");
+
+                    return Err(());
+                }
+            };
+
+        let modified_at = (
+            Self::modified_at(certificate_path)?,
+            Self::modified_at(key_path)?,
+        );
+
+        // Files did not change since last load? (skip reload)
+        if self
+            .current
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|current| current.modified_at)
+            == Some(modified_at)
+        {
+            return Ok(());
+        }
+
+        match Self::load(certificate_path, key_path) {
+            Ok(key) => {
+                info!("loaded tls certificate from: {}", certificate_path);
+
+                *self.current.write().unwrap() = Some(DNSTLSCertificateLoaded {
+                    key: Arc::new(key),
+                    modified_at,
+                });
+
+                Ok(())
+            }
+            Err(err) => {
+                // Notice: the previous certificate is kept if the new one is invalid.
+                error!(
+                    "failed loading tls certificate from: {}: {}",
+                    certificate_path, err
+                );
+
+                Err(())
+            }
+        }
+    }
+
+    fn load(certificate_path: &str, key_path: &str) -> Result<CertifiedKey, String> {
+        let certificates: Vec<Certificate> = rustls_pemfile::certs(&mut BufReader::new(
+            File::open(certificate_path).map_err(|err| err.to_string())?,
+        ))
+        .map_err(|err| err.to_string())?
+        .into_iter()
+        .map(Certificate)
+        .collect();
+
+        if certificates.is_empty() {
+            return Err("no certificate found".to_string());
+        }
+
+        let key = rustls_pemfile::read_all(&mut BufReader::new(
+            File::open(key_path).map_err(|err| err.to_string())?,
+        ))
+        .map_err(|err| err.to_string())?
+        .into_iter()
+        .find_map(|item| match item {
+            Item::RSAKey(key) | Item::PKCS8Key(key) | Item::ECKey(key) => Some(PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or("no private key found")?;
+
+        let signing_key = sign::any_supported_type(&key).map_err(|err| err.to_string())?;
+
+        Ok(CertifiedKey::new(certificates, signing_key))
+    }
+
+    fn modified_at(path: &str) -> Result<SystemTime, ()> {
+        fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .map_err(|err| error!("could not read tls file: {}: {}", path, err))
+    }
+}
+
+impl ResolvesServerCert for DNSTLSCertificate {
+    fn resolve(&self, _: ClientHello) -> Option<Arc<CertifiedKey>> {
+        self.current
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|current| current.key.clone())
+    }
+}
@@ -0,0 +1,740 @@
+// Constellation
+//
+// Pluggable authoritative DNS server
+// Copyright: 2026, Valerian Saliou <valerian@valeriansaliou.name>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use hickory_proto::op::{Header, Message, ResponseCode};
+use hickory_proto::rr::dnssec::rdata::tsig::{
+    make_tsig_record, message_tbs, signed_bitmessage_to_buf, TsigAlgorithm, TSIG,
+};
+use hickory_proto::rr::dnssec::rdata::DNSSECRData;
+use hickory_proto::rr::dnssec::tsig::TSigner;
+use hickory_proto::rr::{
+    DNSClass, LowerName, Name, RData, Record, RecordType as HickoryRecordType,
+};
+use hickory_server::server::Request;
+use std::collections::{HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::code::CodeName;
+use super::metrics::{MetricsValue, METRICS_STORE};
+use super::record::{RecordName, RecordType, RecordValue, RecordValues};
+use super::zone::ZoneName;
+use crate::config::config::{ConfigDNSUpdateKey, ConfigDNSUpdateKeyAlgorithm};
+use crate::store::store::{StoreAuthor, StoreError, StoreOperation, StoreRecord};
+use crate::APP_CONF;
+use crate::APP_STORE;
+
+const TSIG_FUDGE: u16 = 300;
+
+pub struct DNSUpdate;
+
+struct DNSUpdateKey {
+    config: ConfigDNSUpdateKey,
+    signer: TSigner,
+    request_mac: Vec<u8>,
+}
+
+type DNSUpdateChanges = HashMap<(RecordName, RecordType), Option<StoreRecord>>;
+type DNSUpdateReads = HashMap<(RecordName, RecordType), Option<u64>>;
+
+impl DNSUpdate {
+    pub async fn handle(
+        request: &Request,
+        message: &[u8],
+        origin: Option<&Name>,
+    ) -> Option<Vec<u8>> {
+        info!("update request with identifier: {}", request.id());
+
+        // #1. Authenticate update request (all updates must be signed with a known TSIG key)
+        let key = match Self::authenticate(request, message) {
+            Ok(key) => key,
+            Err(code) => return Self::respond(request, None, code, None),
+        };
+
+        // #2. Acquire updated zone (zone must be served, and allowed for key)
+        let zone = request.query();
+
+        if zone.query_type() != HickoryRecordType::SOA {
+            return Self::respond(request, None, ResponseCode::FormErr, Some(&key));
+        }
+
+        let (origin, zone_name) =
+            match origin.map(|origin| (origin, ZoneName::from_hickory(&LowerName::new(origin)))) {
+                Some((origin, Some(zone_name))) => (origin, zone_name),
+                _ => {
+                    warn!("update request for unknown zone: {}", zone.name());
+
+                    return Self::respond(request, None, ResponseCode::NotAuth, Some(&key));
+                }
+            };
+
+        if !key.config.zones.contains(&zone_name) {
+            warn!(
+                "update key: {} is not allowed to update zone: {}",
+                key.config.name,
+                zone_name.to_str()
+            );
+
+            return Self::respond(request, Some(&zone_name), ResponseCode::Refused, Some(&key));
+        }
+
+        // #3. Check prerequisites, prescan and then apply updates
+        // Notice: the revisions of all records read along the way are kept, so that the update \
+        //   is only committed if none of them changed in the meantime.
+        let mut reads = DNSUpdateReads::new();
+
+        let code = match Self::check_prerequisites(request, origin, &zone_name, &mut reads).await {
+            Ok(_) => match Self::prescan_updates(request, origin, &key) {
+                Ok(_) => {
+                    match Self::apply_updates(request, origin, &zone_name, &key, reads).await {
+                        Ok(_) => ResponseCode::NoError,
+                        Err(code) => code,
+                    }
+                }
+                Err(code) => code,
+            },
+            Err(code) => code,
+        };
+
+        info!(
+            "update request: {} with key: {} on zone: {} got code: {}",
+            request.id(),
+            key.config.name,
+            zone_name.to_str(),
+            code
+        );
+
+        Self::respond(request, Some(&zone_name), code, Some(&key))
+    }
+
+    fn authenticate(request: &Request, message: &[u8]) -> Result<DNSUpdateKey, ResponseCode> {
+        // Acquire TSIG record (unsigned updates are refused)
+        let tsig_record = request
+            .sig0()
+            .iter()
+            .find(|record| record.record_type() == HickoryRecordType::TSIG);
+
+        let (tsig_name, tsig) = match tsig_record.map(|record| (record.name(), record.data())) {
+            Some((name, Some(RData::DNSSEC(DNSSECRData::TSIG(tsig))))) => (name, tsig),
+            _ => {
+                warn!("refused unsigned update request: {}", request.id());
+
+                return Err(ResponseCode::Refused);
+            }
+        };
+
+        // Acquire matching key from configuration
+        let key_config = APP_CONF
+            .load()
+            .dns
+            .update
+            .key
+            .iter()
+            .find(|key| {
+                Name::parse(&key.name, Some(&Name::root()))
+                    .map(|name| LowerName::new(&name) == LowerName::new(tsig_name))
+                    .unwrap_or(false)
+                    && &Self::key_algorithm(&key.algorithm) == tsig.algorithm()
+            })
+            .cloned()
+            .ok_or_else(|| {
+                warn!(
+                    "update request: {} signed with unknown key: {}",
+                    request.id(),
+                    tsig_name
+                );
+
+                ResponseCode::NotAuth
+            })?;
+
+        let signer = BASE64
+            .decode(&key_config.secret)
+            .ok()
+            .and_then(|secret| {
+                TSigner::new(
+                    secret,
+                    Self::key_algorithm(&key_config.algorithm),
+                    tsig_name.to_owned(),
+                    TSIG_FUDGE,
+                )
+                .ok()
+            })
+            .ok_or_else(|| {
+                error!("invalid secret for update key: {}", key_config.name);
+
+                ResponseCode::ServFail
+            })?;
+
+        // Verify signature over the raw message, and then check signing time
+        let (tbv, _) =
+            signed_bitmessage_to_buf(None, message, true).or(Err(ResponseCode::FormErr))?;
+
+        if signer.verify(&tbv, tsig.mac()).is_err() {
+            warn!(
+                "update request: {} has an invalid signature for key: {}",
+                request.id(),
+                key_config.name
+            );
+
+            return Err(ResponseCode::NotAuth);
+        }
+
+        let time_now = Self::time_now();
+
+        if time_now + (tsig.fudge() as u64) < tsig.time()
+            || tsig.time() + (tsig.fudge() as u64) < time_now
+        {
+            warn!(
+                "update request: {} signed with key: {} is out of time window",
+                request.id(),
+                key_config.name
+            );
+
+            return Err(ResponseCode::NotAuth);
+        }
+
+        debug!(
+            "authenticated update request: {} with key: {}",
+            request.id(),
+            key_config.name
+        );
+
+        Ok(DNSUpdateKey {
+            config: key_config,
+            signer,
+            request_mac: tsig.mac().to_vec(),
+        })
+    }
+
+    async fn check_prerequisites(
+        request: &Request,
+        origin: &Name,
+        zone_name: &ZoneName,
+        reads: &mut DNSUpdateReads,
+    ) -> Result<(), ResponseCode> {
+        // Value-dependent prerequisites must be compared as whole RRsets, thus they are grouped
+        let mut rrsets: HashMap<(RecordName, RecordType), HashSet<RecordValue>> = HashMap::new();
+
+        for prerequisite in request.answers() {
+            if prerequisite.ttl() != 0 {
+                return Err(ResponseCode::FormErr);
+            }
+            if !origin.zone_of(prerequisite.name()) {
+                return Err(ResponseCode::NotZone);
+            }
+
+            let (class, kind) = (prerequisite.dns_class(), prerequisite.record_type());
+            let record_name = Self::record_name(origin, prerequisite.name());
+
+            match class {
+                DNSClass::ANY | DNSClass::NONE => {
+                    if prerequisite.data().is_some() {
+                        return Err(ResponseCode::FormErr);
+                    }
+
+                    let exists = if kind == HickoryRecordType::ANY {
+                        // Check if name is in use (ie. if it has any record)
+                        match record_name {
+                            Some(ref record_name) => {
+                                Self::get_name_records(zone_name, record_name, reads)
+                                    .await?
+                                    .iter()
+                                    .any(|record| record.is_some())
+                            }
+                            None => false,
+                        }
+                    } else {
+                        // Check if RRset exists (value independent)
+                        match (record_name, RecordType::from_hickory(&kind)) {
+                            (Some(record_name), Some(record_type)) => {
+                                Self::get_record(zone_name, &record_name, &record_type, reads)
+                                    .await?
+                                    .is_some()
+                            }
+                            _ => false,
+                        }
+                    };
+
+                    match (class, kind, exists) {
+                        (DNSClass::ANY, HickoryRecordType::ANY, false) => {
+                            return Err(ResponseCode::NXDomain)
+                        }
+                        (DNSClass::ANY, _, false) => return Err(ResponseCode::NXRRSet),
+                        (DNSClass::NONE, HickoryRecordType::ANY, true) => {
+                            return Err(ResponseCode::YXDomain)
+                        }
+                        (DNSClass::NONE, _, true) => return Err(ResponseCode::YXRRSet),
+                        _ => {}
+                    }
+                }
+                DNSClass::IN => {
+                    // Stack RRset value (value dependent)
+                    match (
+                        record_name,
+                        RecordType::from_hickory(&kind),
+                        prerequisite.data(),
+                    ) {
+                        (Some(record_name), Some(record_type), Some(data)) => {
+                            let value = RecordValue::from_hickory(&record_type, data)
+                                .ok_or(ResponseCode::FormErr)?;
+
+                            rrsets
+                                .entry((record_name, record_type))
+                                .or_default()
+                                .insert(value);
+                        }
+                        _ => return Err(ResponseCode::NXRRSet),
+                    }
+                }
+                _ => return Err(ResponseCode::FormErr),
+            }
+        }
+
+        // Compare value-dependent RRsets with stored values
+        for ((record_name, record_type), values) in rrsets.iter() {
+            let stored_values = Self::get_record(zone_name, record_name, record_type, reads)
+                .await?
+                .map(|record| {
+                    record
+                        .values
+                        .iter()
+                        .cloned()
+                        .collect::<HashSet<RecordValue>>()
+                });
+
+            if stored_values.as_ref() != Some(values) {
+                return Err(ResponseCode::NXRRSet);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn prescan_updates(
+        request: &Request,
+        origin: &Name,
+        key: &DNSUpdateKey,
+    ) -> Result<(), ResponseCode> {
+        for update in request.name_servers() {
+            if !origin.zone_of(update.name()) {
+                return Err(ResponseCode::NotZone);
+            }
+
+            let kind = update.record_type();
+
+            let is_meta_type = match kind {
+                HickoryRecordType::AXFR | HickoryRecordType::IXFR => true,
+                HickoryRecordType::Unknown(code) => code == 253 || code == 254,
+                _ => false,
+            };
+
+            match update.dns_class() {
+                DNSClass::IN => {
+                    if is_meta_type || kind == HickoryRecordType::ANY || update.data().is_none() {
+                        return Err(ResponseCode::FormErr);
+                    }
+                }
+                DNSClass::ANY => {
+                    if is_meta_type || update.ttl() != 0 || update.data().is_some() {
+                        return Err(ResponseCode::FormErr);
+                    }
+                }
+                DNSClass::NONE => {
+                    if is_meta_type || kind == HickoryRecordType::ANY || update.ttl() != 0 {
+                        return Err(ResponseCode::FormErr);
+                    }
+                }
+                _ => return Err(ResponseCode::FormErr),
+            }
+
+            // Only supported record types can be stored (SOA and NS are served from config)
+            if kind != HickoryRecordType::ANY && RecordType::from_hickory(&kind).is_none() {
+                warn!("refused update for unsupported record type: {}", kind);
+
+                return Err(ResponseCode::Refused);
+            }
+
+            // Check that record name is allowed for key
+            let is_allowed = match (Self::record_name(origin, update.name()), &key.config.names) {
+                (Some(record_name), Some(names)) => {
+                    names.iter().any(|pattern| pattern.matches(&record_name))
+                }
+                (Some(_), None) => true,
+                (None, _) => false,
+            };
+
+            if !is_allowed {
+                warn!(
+                    "update key: {} is not allowed to update name: {}",
+                    key.config.name,
+                    update.name()
+                );
+
+                return Err(ResponseCode::Refused);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn apply_updates(
+        request: &Request,
+        origin: &Name,
+        zone_name: &ZoneName,
+        key: &DNSUpdateKey,
+        mut reads: DNSUpdateReads,
+    ) -> Result<(), ResponseCode> {
+        // Apply all updates to a working set first, then commit changed records to the store
+        let mut changes: DNSUpdateChanges = HashMap::new();
+
+        for update in request.name_servers() {
+            // Notice: names were already checked to be valid in the prescan pass
+            let record_name =
+                Self::record_name(origin, update.name()).ok_or(ResponseCode::FormErr)?;
+
+            match (update.dns_class(), update.record_type()) {
+                (DNSClass::ANY, HickoryRecordType::ANY) => {
+                    // Delete all RRsets from a name
+                    for record_type in RecordType::list_choices() {
+                        changes.insert((record_name.to_owned(), record_type), None);
+                    }
+                }
+                (DNSClass::ANY, kind) => {
+                    // Delete an RRset
+                    if let Some(record_type) = RecordType::from_hickory(&kind) {
+                        changes.insert((record_name, record_type), None);
+                    }
+                }
+                (DNSClass::NONE, kind) => {
+                    // Delete a value from an RRset
+                    if let (Some(record_type), Some(data)) =
+                        (RecordType::from_hickory(&kind), update.data())
+                    {
+                        let value = RecordValue::from_hickory(&record_type, data)
+                            .ok_or(ResponseCode::FormErr)?;
+
+                        let record = Self::get_change(
+                            &mut changes,
+                            &mut reads,
+                            zone_name,
+                            &record_name,
+                            &record_type,
+                        )
+                        .await?;
+
+                        if let Some(ref mut record_inner) = record {
+                            record_inner.values = RecordValues::from_list(
+                                record_inner
+                                    .values
+                                    .iter()
+                                    .filter(|existing| *existing != &value)
+                                    .cloned()
+                                    .collect(),
+                            );
+
+                            if record_inner.values.is_empty() {
+                                *record = None;
+                            }
+                        }
+                    }
+                }
+                (_, kind) => {
+                    // Add a value to an RRset
+                    if let (Some(record_type), Some(data)) =
+                        (RecordType::from_hickory(&kind), update.data())
+                    {
+                        Self::apply_update_add(
+                            &mut changes,
+                            &mut reads,
+                            zone_name,
+                            record_name,
+                            record_type,
+                            data,
+                            update,
+                        )
+                        .await?;
+                    }
+                }
+            }
+        }
+
+        // Commit all changes to the store
+        // Notice: changes are committed at once, so that resolvers never observe a half-applied \
+        //   update (as required by RFC 2136).
+        let mut operations = Vec::new();
+
+        for ((record_name, record_type), record) in changes.into_iter() {
+            match record {
+                Some(record) => {
+                    debug!(
+                        "update will set record: {:?} on type: {:?} in zone: {:?}",
+                        record_name, record_type, zone_name
+                    );
+
+                    operations.push(StoreOperation::Set(Box::new(record)));
+                }
+                None => {
+                    debug!(
+                        "update will remove record: {:?} on type: {:?} in zone: {:?}",
+                        record_name, record_type, zone_name
+                    );
+
+                    operations.push(StoreOperation::Remove(record_name, record_type));
+                }
+            }
+        }
+
+        let author = StoreAuthor {
+            identity: format!("update:{}", key.config.name),
+            source: Some(request.src().ip()),
+        };
+
+        let expected: Vec<(RecordName, RecordType, Option<u64>)> = reads
+            .into_iter()
+            .map(|((record_name, record_type), revision)| (record_name, record_type, revision))
+            .collect();
+
+        match APP_STORE
+            .apply_expected(zone_name, operations, &expected, &author)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(StoreError::PreconditionFailed) => {
+                warn!(
+                    "could not commit update in zone: {:?} as records changed concurrently",
+                    zone_name
+                );
+
+                Err(ResponseCode::ServFail)
+            }
+            Err(_) => {
+                error!("failed committing update in zone: {:?}", zone_name);
+
+                Err(ResponseCode::ServFail)
+            }
+        }
+    }
+
+    async fn apply_update_add(
+        changes: &mut DNSUpdateChanges,
+        reads: &mut DNSUpdateReads,
+        zone_name: &ZoneName,
+        record_name: RecordName,
+        record_type: RecordType,
+        data: &RData,
+        update: &Record,
+    ) -> Result<(), ResponseCode> {
+        let value = RecordValue::from_hickory(&record_type, data).ok_or(ResponseCode::FormErr)?;
+
+        // A CNAME cannot coexist with other data at the same name, thus ignore conflicting adds
+        for other_type in RecordType::list_choices() {
+            if (other_type == RecordType::CNAME) != (record_type == RecordType::CNAME)
+                && Self::get_change(changes, reads, zone_name, &record_name, &other_type)
+                    .await?
+                    .is_some()
+            {
+                info!(
+                    "ignored update add on record: {:?} on type: {:?} because of cname conflict",
+                    record_name, record_type
+                );
+
+                return Ok(());
+            }
+        }
+
+        let record =
+            Self::get_change(changes, reads, zone_name, &record_name, &record_type).await?;
+
+        match record {
+            Some(ref mut record_inner) => {
+                // Notice: a CNAME holds a single value, which gets replaced
+                let mut values = if record_type == RecordType::CNAME {
+                    Vec::new()
+                } else {
+                    record_inner.values.to_vec()
+                };
+
+                if !values.contains(&value) {
+                    values.push(value);
+                }
+
+                record_inner.ttl = Some(update.ttl());
+                record_inner.values = RecordValues::from_list(values);
+            }
+            None => {
+                *record = Some(StoreRecord {
+                    kind: record_type,
+                    name: record_name,
+                    ttl: Some(update.ttl()),
+                    flatten: None,
+                    blackhole: None,
+                    regions: None,
+                    rescue: None,
+                    values: RecordValues::from_list(vec![value]),
+                    revision: None,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn get_change<'a>(
+        changes: &'a mut DNSUpdateChanges,
+        reads: &mut DNSUpdateReads,
+        zone_name: &ZoneName,
+        record_name: &RecordName,
+        record_type: &RecordType,
+    ) -> Result<&'a mut Option<StoreRecord>, ResponseCode> {
+        let change_key = (record_name.to_owned(), record_type.to_owned());
+
+        if !changes.contains_key(&change_key) {
+            let record = Self::get_record(zone_name, record_name, record_type, reads).await?;
+
+            changes.insert(change_key.to_owned(), record);
+        }
+
+        Ok(changes.get_mut(&change_key).unwrap())
+    }
+
+    async fn get_record(
+        zone_name: &ZoneName,
+        record_name: &RecordName,
+        record_type: &RecordType,
+        reads: &mut DNSUpdateReads,
+    ) -> Result<Option<StoreRecord>, ResponseCode> {
+        let mut records = Self::get_records(
+            zone_name,
+            vec![(record_name.to_owned(), record_type.to_owned())],
+            reads,
+        )
+        .await?;
+
+        Ok(records.pop().flatten())
+    }
+
+    async fn get_name_records(
+        zone_name: &ZoneName,
+        record_name: &RecordName,
+        reads: &mut DNSUpdateReads,
+    ) -> Result<Vec<Option<StoreRecord>>, ResponseCode> {
+        let keys = RecordType::list_choices()
+            .into_iter()
+            .map(|record_type| (record_name.to_owned(), record_type))
+            .collect();
+
+        Self::get_records(zone_name, keys, reads).await
+    }
+
+    async fn get_records(
+        zone_name: &ZoneName,
+        keys: Vec<(RecordName, RecordType)>,
+        reads: &mut DNSUpdateReads,
+    ) -> Result<Vec<Option<StoreRecord>>, ResponseCode> {
+        // Notice: records are read from the remote store, as the local cache might be stale, and \
+        //   the revision they were read at is kept (the first read is the one that counts).
+        let records = APP_STORE
+            .get_records(zone_name, &keys)
+            .await
+            .or(Err(ResponseCode::ServFail))?;
+
+        for (key, record) in keys.into_iter().zip(records.iter()) {
+            reads
+                .entry(key)
+                .or_insert(record.as_ref().and_then(|record| record.revision));
+        }
+
+        Ok(records)
+    }
+
+    fn respond(
+        request: &Request,
+        zone_name: Option<&ZoneName>,
+        code: ResponseCode,
+        key: Option<&DNSUpdateKey>,
+    ) -> Option<Vec<u8>> {
+        // Stack answer code to metrics?
+        if let Some(zone_name) = zone_name {
+            METRICS_STORE.stack(
+                zone_name,
+                MetricsValue::AnswerCode(&CodeName::from_hickory(&code)),
+            );
+        }
+
+        let mut header = Header::response_from_request(request.header());
+
+        header.set_response_code(code);
+
+        let mut message = Message::new();
+
+        message
+            .set_header(header)
+            .add_query(request.query().original().to_owned());
+
+        // Sign response with the request key? (the request MAC is part of the signed data)
+        if let Some(key) = key {
+            let tsig = TSIG::new(
+                key.signer.algorithm().to_owned(),
+                Self::time_now(),
+                TSIG_FUDGE,
+                Vec::new(),
+                request.id(),
+                0,
+                Vec::new(),
+            );
+
+            match message_tbs(
+                Some(&key.request_mac),
+                &message,
+                &tsig,
+                key.signer.signer_name(),
+            )
+            .and_then(|tbs| key.signer.sign(&tbs))
+            {
+                Ok(mac) => {
+                    message.add_tsig(make_tsig_record(
+                        key.signer.signer_name().to_owned(),
+                        tsig.set_mac(mac),
+                    ));
+                }
+                Err(err) => {
+                    error!("could not sign update response: {}", err);
+
+                    return None;
+                }
+            }
+        }
+
+        match message.to_vec() {
+            Ok(response) => Some(response),
+            Err(err) => {
+                error!("could not encode update response: {}", err);
+
+                None
+            }
+        }
+    }
+
+    fn record_name(origin: &Name, name: &Name) -> Option<RecordName> {
+        RecordName::from_hickory(&LowerName::new(origin), &LowerName::new(name))
+    }
+
+    fn key_algorithm(algorithm: &ConfigDNSUpdateKeyAlgorithm) -> TsigAlgorithm {
+        match algorithm {
+            ConfigDNSUpdateKeyAlgorithm::HmacSha256 => TsigAlgorithm::HmacSha256,
+            ConfigDNSUpdateKeyAlgorithm::HmacSha384 => TsigAlgorithm::HmacSha384,
+            ConfigDNSUpdateKeyAlgorithm::HmacSha512 => TsigAlgorithm::HmacSha512,
+        }
+    }
+
+    fn time_now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0)
+    }
+}
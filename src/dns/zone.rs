@@ -10,7 +10,7 @@ use serde::de::{Error as DeserializeError, Unexpected, Visitor};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::{fmt, str};
 
-use crate::APP_CONF;
+use super::registry::DNS_REGISTRY;
 
 lazy_static! {
     static ref ZONE_NAME_REGEX: Regex =
@@ -58,7 +58,7 @@ impl ZoneName {
         //   resulting in an infinite loop while parsing the configuration file. Thus, the \
         //   specialized `from_str_exists` method is used for later `ZoneName` parsing (ie. after \
         //   startup).
-        if APP_CONF.dns.zone_exists(value) {
+        if DNS_REGISTRY.zone_exists(value) {
             Self::from_str(value)
         } else {
             None
@@ -0,0 +1,451 @@
+// Constellation
+//
+// Pluggable authoritative DNS server
+// Copyright: 2026, Valerian Saliou <valerian@valeriansaliou.name>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use hickory_proto::rr::{LowerName, Name, RecordSet, RecordType as HickoryRecordType};
+use hickory_proto::serialize::txt::Parser;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::fs;
+
+use super::record::{
+    RecordBlackhole, RecordName, RecordRegions, RecordType, RecordValue, RecordValues,
+};
+use super::registry::DNS_REGISTRY;
+use super::zone::{ZoneName, ZoneNameExists};
+use crate::store::store::{StoreAuthor, StoreError, StoreOperation, StoreRecord};
+use crate::{APP_CONF, APP_STORE};
+
+static METADATA_COMMENT_PREFIX: &str = "; constellation: ";
+static INCLUDE_DIRECTIVE: &str = "$INCLUDE";
+
+const EXPORT_PAGE_SIZE: usize = 1000;
+
+pub struct DNSZoneFile;
+
+pub struct DNSZoneFileImportBuilder;
+
+pub struct DNSZoneFileImport {
+    zone: String,
+    path: String,
+    dry_run: bool,
+}
+
+pub enum DNSZoneFileError {
+    Invalid(String),
+    Store,
+}
+
+#[derive(Serialize, Default)]
+pub struct DNSZoneFileImportReport {
+    pub created: Vec<DNSZoneFileImportRecord>,
+    pub changed: Vec<DNSZoneFileImportRecord>,
+    pub unchanged: Vec<DNSZoneFileImportRecord>,
+    pub unsupported: Vec<DNSZoneFileImportUnsupported>,
+}
+
+#[derive(Serialize)]
+pub struct DNSZoneFileImportRecord {
+    name: RecordName,
+
+    #[serde(rename = "type")]
+    _type: RecordType,
+}
+
+#[derive(Serialize)]
+pub struct DNSZoneFileImportUnsupported {
+    name: String,
+
+    #[serde(rename = "type")]
+    _type: String,
+
+    reason: &'static str,
+}
+
+#[derive(Serialize, Deserialize)]
+struct DNSZoneFileMetadata {
+    name: RecordName,
+
+    #[serde(rename = "type")]
+    _type: RecordType,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    flatten: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    blackhole: Option<RecordBlackhole>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    regions: Option<RecordRegions>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rescue: Option<RecordValues>,
+}
+
+impl DNSZoneFileImportBuilder {
+    pub fn new(zone: &str, path: &str, dry_run: bool) -> DNSZoneFileImport {
+        DNSZoneFileImport {
+            zone: zone.to_owned(),
+            path: path.to_owned(),
+            dry_run,
+        }
+    }
+}
+
+impl DNSZoneFileImport {
+    #[tokio::main]
+    pub async fn run(&self) -> Result<(), ()> {
+        info!(
+            "importing zone file: {} into zone: {} (dry run: {})",
+            self.path, self.zone, self.dry_run
+        );
+
+        let zone_file = fs::read_to_string(&self.path).map_err(|err| {
+            error!("could not read zone file: {}", err);
+        })?;
+
+        // Load stored zones (as the zone registry is not synced when running a command)
+        DNS_REGISTRY.sync().await.map_err(|_| {
+            error!("could not load zones from store");
+        })?;
+
+        let zone_name = ZoneNameExists::from_str(&self.zone)
+            .map(|zone_name| zone_name.into_inner())
+            .ok_or_else(|| {
+                error!("zone does not exist: {}", self.zone);
+            })?;
+
+        let author = StoreAuthor {
+            identity: "cli".to_string(),
+            source: None,
+        };
+
+        match DNSZoneFile::import(&zone_name, &zone_file, self.dry_run, &author).await {
+            Ok(report) => {
+                info!(
+                    "imported zone file (created: {}, changed: {}, unchanged: {}, unsupported: {})",
+                    report.created.len(),
+                    report.changed.len(),
+                    report.unchanged.len(),
+                    report.unsupported.len()
+                );
+
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&report).expect("could not serialize report")
+                );
+
+                Ok(())
+            }
+            Err(DNSZoneFileError::Invalid(reason)) => {
+                error!("invalid zone file: {}", reason);
+
+                Err(())
+            }
+            Err(DNSZoneFileError::Store) => {
+                error!("could not import zone file records into store");
+
+                Err(())
+            }
+        }
+    }
+}
+
+impl DNSZoneFile {
+    pub async fn export(zone_name: &ZoneName) -> Result<String, StoreError> {
+        let origin =
+            Name::parse(zone_name.to_str(), Some(&Name::root())).or(Err(StoreError::Corrupted))?;
+
+        let mut zone_file = String::new();
+
+        writeln!(zone_file, "; Zone: {}", origin).ok();
+        writeln!(zone_file, "$ORIGIN {}", origin).ok();
+        writeln!(
+            zone_file,
+            "$TTL {}",
+            APP_CONF.load().dns.zone_record_ttl(zone_name.to_str())
+        )
+        .ok();
+
+        // #1. Dump SOA and NS records (from the zone authority)
+        if let Some(authority) = DNS_REGISTRY.get_authority(&LowerName::new(&origin)) {
+            writeln!(zone_file, "\n; Zone authority").ok();
+
+            for record_type in [HickoryRecordType::SOA, HickoryRecordType::NS] {
+                for record_set in authority.records().await.values() {
+                    if record_set.record_type() == record_type {
+                        for record in record_set.records_without_rrsigs() {
+                            writeln!(zone_file, "{}", record).ok();
+                        }
+                    }
+                }
+            }
+        }
+
+        // #2. Dump all records (from the store)
+        writeln!(zone_file, "\n; Zone records").ok();
+
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let entries = APP_STORE
+                .list_records(zone_name, cursor.as_deref(), None, EXPORT_PAGE_SIZE)
+                .await?;
+
+            let is_last_page = entries.len() < EXPORT_PAGE_SIZE;
+
+            cursor = entries.last().map(|(entry, _)| entry.to_owned());
+
+            let keys: Vec<(RecordName, RecordType)> =
+                entries.into_iter().filter_map(|(_, key)| key).collect();
+
+            for record in APP_STORE
+                .get_records(zone_name, &keys)
+                .await?
+                .into_iter()
+                .flatten()
+            {
+                Self::export_record(&mut zone_file, zone_name, &origin, record);
+            }
+
+            if is_last_page {
+                break;
+            }
+        }
+
+        Ok(zone_file)
+    }
+
+    pub async fn import(
+        zone_name: &ZoneName,
+        zone_file: &str,
+        dry_run: bool,
+        author: &StoreAuthor,
+    ) -> Result<DNSZoneFileImportReport, DNSZoneFileError> {
+        let origin = Name::parse(zone_name.to_str(), Some(&Name::root()))
+            .or(Err(DNSZoneFileError::Store))?;
+        let zone_origin = LowerName::new(&origin);
+        let zone_ttl = APP_CONF.load().dns.zone_record_ttl(zone_name.to_str());
+
+        // Refuse includes, as they would let the zone file read arbitrary files from this host
+        if zone_file.lines().any(|line| {
+            line.trim_start()
+                .to_uppercase()
+                .starts_with(INCLUDE_DIRECTIVE)
+        }) {
+            return Err(DNSZoneFileError::Invalid(
+                "include directives are not supported".to_string(),
+            ));
+        }
+
+        let mut metadata = Self::import_metadata(zone_file)?;
+
+        let (_, record_sets) = Parser::new(zone_file, None, Some(origin))
+            .parse()
+            .map_err(|err| DNSZoneFileError::Invalid(err.to_string()))?;
+
+        let mut report = DNSZoneFileImportReport::default();
+        let mut records = Vec::new();
+
+        for record_set in record_sets.values() {
+            match Self::import_record(&zone_origin, zone_ttl, record_set, &mut metadata) {
+                Ok(record) => records.push(record),
+                Err(reason) => report.unsupported.push(DNSZoneFileImportUnsupported {
+                    name: record_set.name().to_string(),
+                    _type: record_set.record_type().to_string(),
+                    reason,
+                }),
+            }
+        }
+
+        // Read existing records at once, to tell created, changed and unchanged records apart
+        let keys: Vec<(RecordName, RecordType)> = records
+            .iter()
+            .map(|record| (record.name.to_owned(), record.kind.to_owned()))
+            .collect();
+
+        let existing_records = APP_STORE
+            .get_records(zone_name, &keys)
+            .await
+            .or(Err(DNSZoneFileError::Store))?;
+
+        let mut operations = Vec::new();
+
+        for (record, existing) in records.into_iter().zip(existing_records) {
+            let entry = DNSZoneFileImportRecord {
+                name: record.name.to_owned(),
+                _type: record.kind.to_owned(),
+            };
+
+            match existing {
+                Some(ref existing) if Self::is_record_unchanged(existing, &record, zone_ttl) => {
+                    report.unchanged.push(entry);
+
+                    continue;
+                }
+                Some(_) => report.changed.push(entry),
+                None => report.created.push(entry),
+            }
+
+            operations.push(StoreOperation::Set(Box::new(record)));
+        }
+
+        // Write all created and changed records at once (either all or none get imported)
+        if !dry_run && !operations.is_empty() {
+            APP_STORE
+                .apply(zone_name, operations, author)
+                .await
+                .or(Err(DNSZoneFileError::Store))?;
+        }
+
+        Ok(report)
+    }
+
+    fn import_metadata(
+        zone_file: &str,
+    ) -> Result<HashMap<(RecordName, RecordType), DNSZoneFileMetadata>, DNSZoneFileError> {
+        let mut metadata = HashMap::new();
+
+        for line in zone_file.lines() {
+            if let Some(line_json) = line.trim_start().strip_prefix(METADATA_COMMENT_PREFIX) {
+                let entry: DNSZoneFileMetadata =
+                    serde_json::from_str(line_json).map_err(|err| {
+                        DNSZoneFileError::Invalid(format!("invalid metadata comment: {}", err))
+                    })?;
+
+                metadata.insert((entry.name.to_owned(), entry._type.to_owned()), entry);
+            }
+        }
+
+        Ok(metadata)
+    }
+
+    fn import_record(
+        zone_origin: &LowerName,
+        zone_ttl: u32,
+        record_set: &RecordSet,
+        metadata: &mut HashMap<(RecordName, RecordType), DNSZoneFileMetadata>,
+    ) -> Result<StoreRecord, &'static str> {
+        let name = LowerName::new(record_set.name());
+
+        if !zone_origin.zone_of(&name) {
+            return Err("name is out of zone");
+        }
+
+        // Notice: zone authority records are generated from the configuration, and thus \
+        //   cannot be imported.
+        if &name == zone_origin
+            && (record_set.record_type() == HickoryRecordType::SOA
+                || record_set.record_type() == HickoryRecordType::NS)
+        {
+            return Err("zone authority is not imported");
+        }
+
+        let kind = RecordType::from_hickory(&record_set.record_type())
+            .ok_or("record type is not supported")?;
+        let record_name =
+            RecordName::from_hickory(zone_origin, &name).ok_or("record name is not supported")?;
+
+        let (mut values, mut ttl) = (Vec::new(), None);
+
+        for record in record_set.records_without_rrsigs() {
+            let value = record
+                .data()
+                .and_then(|data| RecordValue::from_hickory(&kind, data))
+                .ok_or("record value is not supported")?;
+
+            values.push(value);
+
+            // Notice: the lowest TTL wins, as record sets cannot hold varying TTLs.
+            ttl = Some(ttl.map_or(record.ttl(), |ttl: u32| ttl.min(record.ttl())));
+        }
+
+        if values.is_empty() {
+            return Err("record value is empty");
+        }
+
+        let metadata = metadata.remove(&(record_name.to_owned(), kind.to_owned()));
+
+        Ok(StoreRecord {
+            kind,
+            name: record_name,
+            ttl: ttl.filter(|ttl| *ttl != zone_ttl),
+            flatten: metadata.as_ref().and_then(|metadata| metadata.flatten),
+            blackhole: metadata
+                .as_ref()
+                .and_then(|metadata| metadata.blackhole.to_owned()),
+            regions: metadata
+                .as_ref()
+                .and_then(|metadata| metadata.regions.to_owned()),
+            rescue: metadata.and_then(|metadata| metadata.rescue),
+            values: RecordValues::from_list(values),
+            revision: None,
+        })
+    }
+
+    fn is_record_unchanged(existing: &StoreRecord, record: &StoreRecord, zone_ttl: u32) -> bool {
+        // Notice: values order is not significant, as values get shuffled when served.
+        existing.ttl.unwrap_or(zone_ttl) == record.ttl.unwrap_or(zone_ttl)
+            && existing.flatten.unwrap_or(false) == record.flatten.unwrap_or(false)
+            && existing.blackhole == record.blackhole
+            && existing.regions == record.regions
+            && existing.rescue == record.rescue
+            && existing.values.iter().collect::<HashSet<&RecordValue>>()
+                == record.values.iter().collect::<HashSet<&RecordValue>>()
+    }
+
+    fn export_record(
+        zone_file: &mut String,
+        zone_name: &ZoneName,
+        origin: &Name,
+        record: StoreRecord,
+    ) {
+        let name = Self::record_name_to_absolute(&record.name, origin);
+        let ttl = record
+            .ttl
+            .unwrap_or_else(|| APP_CONF.load().dns.zone_record_ttl(zone_name.to_str()));
+
+        // Preserve options that cannot be expressed in a zone file as a structured comment
+        // Notice: this comment applies to the record set that follows it.
+        if record.flatten == Some(true)
+            || record.blackhole.is_some()
+            || record.regions.is_some()
+            || record.rescue.is_some()
+        {
+            let metadata = DNSZoneFileMetadata {
+                name: record.name.to_owned(),
+                _type: record.kind.to_owned(),
+                flatten: record.flatten.filter(|flatten| *flatten),
+                blackhole: record.blackhole,
+                regions: record.regions,
+                rescue: record.rescue,
+            };
+
+            if let Ok(metadata) = serde_json::to_string(&metadata) {
+                writeln!(zone_file, "{}{}", METADATA_COMMENT_PREFIX, metadata).ok();
+            }
+        }
+
+        for value in record.values.iter() {
+            writeln!(
+                zone_file,
+                "{} {} IN {} {}",
+                name,
+                ttl,
+                record.kind.to_str().to_uppercase(),
+                value.to_zone_file(&record.kind)
+            )
+            .ok();
+        }
+    }
+
+    fn record_name_to_absolute(record_name: &RecordName, origin: &Name) -> String {
+        // Notice: record names are relative to the zone apex, which is noted '@' (eg. 'a.@')
+        match record_name.to_str().strip_suffix('@') {
+            Some(prefix) => format!("{}{}", prefix, origin),
+            None => record_name.to_str().to_owned(),
+        }
+    }
+}
@@ -42,7 +42,7 @@ impl Locator {
     }
 
     pub fn get_database_full_path() -> PathBuf {
-        Path::new(&APP_CONF.geo.database_path).join(&APP_CONF.geo.database_file)
+        Path::new(&APP_CONF.load().geo.database_path).join(&APP_CONF.load().geo.database_file)
     }
 
     pub fn request_geo_refresh() -> Result<(), MaxMindDBError> {
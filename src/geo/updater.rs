@@ -28,10 +28,11 @@ impl GeoUpdaterBuilder {
 
 impl GeoUpdater {
     pub fn run(&self) {
-        let interval_duration = Duration::from_secs(APP_CONF.geo.update_interval);
+        let interval_duration = Duration::from_secs(APP_CONF.load().geo.update_interval);
 
         // Acquire update URL
         let update_url = APP_CONF
+            .load()
             .geo
             .update_url
             .to_owned()
@@ -73,7 +74,7 @@ impl GeoUpdater {
                 let file_path = file.path().map(|path| path.into_owned());
 
                 if let Ok(path) = file_path {
-                    if path.ends_with(&APP_CONF.geo.database_file) == true {
+                    if path.ends_with(&APP_CONF.load().geo.database_file) {
                         let database_path = Locator::get_database_full_path();
 
                         match file.unpack(&database_path) {
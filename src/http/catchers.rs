@@ -14,7 +14,10 @@ use serde_json;
 
 #[derive(Serialize)]
 pub struct CatcherResponse {
-    error: &'static str,
+    pub error: &'static str,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<&'static str>,
 }
 
 pub struct HTTPCatchers;
@@ -28,6 +31,8 @@ impl HTTPCatchers {
             .handler(Status::NOT_FOUND, Self::not_found)
             .handler(Status::METHOD_NOT_ALLOWED, Self::method_not_allowed)
             .handler(Status::NOT_ACCEPTABLE, Self::not_acceptable)
+            .handler(Status::CONFLICT, Self::conflict)
+            .handler(Status::PRECONDITION_FAILED, Self::precondition_failed)
             .handler(Status::PAYLOAD_TOO_LARGE, Self::payload_too_large)
             .handler(Status::INTERNAL_SERVER_ERROR, Self::internal_server_error)
     }
@@ -36,6 +41,15 @@ impl HTTPCatchers {
         mut response: ServiceResponse<B>,
         reason: &'static str,
     ) -> Result<ErrorHandlerResponse<B>> {
+        // Keep error bodies that were already serialized to JSON (eg. with a message)
+        if response.response().headers().get(CONTENT_TYPE)
+            == Some(&HeaderValue::from_static("application/json"))
+        {
+            return Ok(ErrorHandlerResponse::Response(
+                response.map_into_left_body(),
+            ));
+        }
+
         // Insert JSON MIME type
         response
             .response_mut()
@@ -43,8 +57,11 @@ impl HTTPCatchers {
             .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
 
         // Map new error body
-        let body_json = serde_json::to_string(&CatcherResponse { error: reason })
-            .expect("could not serialize catcher json body");
+        let body_json = serde_json::to_string(&CatcherResponse {
+            error: reason,
+            message: None,
+        })
+        .expect("could not serialize catcher json body");
 
         let error_response = response.map_body(|_, _| BoxBody::new(body_json));
 
@@ -77,6 +94,14 @@ impl HTTPCatchers {
         Self::respond(response, "not_acceptable")
     }
 
+    fn conflict<B>(response: ServiceResponse<B>) -> Result<ErrorHandlerResponse<B>> {
+        Self::respond(response, "conflict")
+    }
+
+    fn precondition_failed<B>(response: ServiceResponse<B>) -> Result<ErrorHandlerResponse<B>> {
+        Self::respond(response, "precondition_failed")
+    }
+
     fn payload_too_large<B>(response: ServiceResponse<B>) -> Result<ErrorHandlerResponse<B>> {
         Self::respond(response, "payload_too_large")
     }
@@ -4,9 +4,10 @@
 // Copyright: 2018, Valerian Saliou <valerian@valeriansaliou.name>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
-use actix_web::dev::ServiceRequest;
+use actix_web::dev::{Service, ServiceRequest};
+use actix_web::http::Method;
 use actix_web::middleware::NormalizePath;
-use actix_web::{rt, App, Error as ActixError, HttpServer};
+use actix_web::{rt, App, Error as ActixError, HttpMessage, HttpServer};
 use actix_web_httpauth::{
     extractors::{
         basic::{BasicAuth, Config as ConfigAuth},
@@ -15,6 +16,7 @@ use actix_web_httpauth::{
     middleware::HttpAuthentication,
 };
 
+use crate::config::config::{ConfigHTTPToken, ConfigHTTPTokenOperation};
 use crate::APP_CONF;
 
 use super::{catchers, routes};
@@ -35,18 +37,66 @@ impl HTTPListen {
         let server = HttpServer::new(|| {
             App::new()
                 .wrap(NormalizePath::trim())
+                .wrap_fn(|request, service| {
+                    let is_write = !matches!(*request.method(), Method::GET | Method::HEAD);
+                    let response = service.call(request);
+
+                    async move {
+                        let response = response.await?;
+
+                        // Log all writes along with their token, whether they succeeded or not
+                        if is_write {
+                            let request = response.request();
+                            let token_name = request
+                                .extensions()
+                                .get::<ConfigHTTPToken>()
+                                .map(|token| token.name.to_owned())
+                                .unwrap_or_default();
+
+                            if response.status().is_success() {
+                                info!(
+                                    "permitted write: {} {} with token: {}",
+                                    request.method(),
+                                    request.path(),
+                                    token_name
+                                );
+                            } else {
+                                warn!(
+                                    "failed write: {} {} with token: {} (status: {})",
+                                    request.method(),
+                                    request.path(),
+                                    token_name,
+                                    response.status()
+                                );
+                            }
+                        }
+
+                        Ok(response)
+                    }
+                })
                 .wrap(HttpAuthentication::basic(authenticate))
                 .wrap(catchers::HTTPCatchers::errors())
+                .service(routes::list_zones)
+                .service(routes::put_zone)
+                .service(routes::delete_zone)
+                .service(routes::list_zone_records)
+                .service(routes::export_zone)
+                .service(routes::import_zone)
                 .service(routes::head_zone_record)
                 .service(routes::get_zone_record)
                 .service(routes::put_zone_record)
+                .service(routes::patch_zone_record)
                 .service(routes::delete_zone_record)
+                .service(routes::batch_zone_records)
+                .service(routes::get_zone_record_history)
+                .service(routes::revert_zone_record)
                 .service(routes::get_metrics_query_types)
                 .service(routes::get_metrics_query_origins)
                 .service(routes::get_metrics_answer_codes)
+                .service(routes::get_metrics_answer_rate_limits)
         })
-        .workers(APP_CONF.http.workers)
-        .bind(APP_CONF.http.inet)
+        .workers(APP_CONF.load().http.workers)
+        .bind(APP_CONF.load().http.inet)
         .unwrap()
         .run();
 
@@ -64,9 +114,41 @@ async fn authenticate(
         ""
     };
 
-    if password == APP_CONF.http.record_token {
+    // Notice: the legacy record token grants all operations on all zones, as named tokens \
+    //   do when they are not scoped.
+    let http_conf = &APP_CONF.load().http;
+
+    let token = http_conf
+        .token
+        .iter()
+        .find(|token| token.secret == password)
+        .cloned()
+        .or_else(|| match http_conf.record_token {
+            Some(ref record_token) if record_token == password => Some(ConfigHTTPToken {
+                name: "record_token".to_string(),
+                secret: record_token.to_owned(),
+                zones: None,
+                operations: vec![
+                    ConfigHTTPTokenOperation::Read,
+                    ConfigHTTPTokenOperation::Write,
+                    ConfigHTTPTokenOperation::Metrics,
+                ],
+                names: None,
+            }),
+            _ => None,
+        });
+
+    if let Some(token) = token {
+        request.extensions_mut().insert(token);
+
         Ok(request)
     } else {
+        warn!(
+            "refused request: {} {} with unknown token",
+            request.method(),
+            request.path()
+        );
+
         let mut error = AuthenticationError::from(
             request
                 .app_data::<ConfigAuth>()
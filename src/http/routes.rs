@@ -4,13 +4,31 @@
 // Copyright: 2018, Valerian Saliou <valerian@valeriansaliou.name>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
-use actix_web::{delete, get, head, put, web, HttpResponse};
+use actix_web::http::header::{ETag, EntityTag, IfMatch, IfNoneMatch};
+use actix_web::{delete, get, head, patch, post, put, web, HttpRequest, HttpResponse};
+use std::net::IpAddr;
 
+use crate::config::config::{ConfigHTTPToken, ConfigHTTPTokenOperation};
 use crate::dns::metrics::{MetricsTimespan, MetricsType, METRICS_STORE};
 use crate::dns::record::{RecordBlackhole, RecordName, RecordRegions, RecordType, RecordValues};
-use crate::dns::zone::ZoneNameExists;
-use crate::store::store::{StoreAccessOrigin, StoreRecord};
-use crate::APP_STORE;
+use crate::dns::registry::DNS_REGISTRY;
+use crate::dns::zone::{ZoneName, ZoneNameExists};
+use crate::dns::zonefile::{DNSZoneFile, DNSZoneFileError};
+use crate::geo::region::RegionCode;
+use crate::store::store::{
+    StoreAccessOrigin, StoreAuthor, StoreCondition, StoreConditionRevisions, StoreError,
+    StoreOperation, StoreRecord,
+};
+use crate::{APP_CONF, APP_STORE};
+
+use super::catchers::CatcherResponse;
+
+static ZONE_FILE_MIME: &str = "text/dns";
+
+const RECORDS_LIST_LIMIT_DEFAULT: usize = 100;
+const RECORDS_LIST_LIMIT_MAXIMUM: usize = 1000;
+const RECORDS_LIST_SCAN_MAXIMUM: usize = 10000;
+const RECORDS_BATCH_OPERATIONS_MAXIMUM: usize = 1000;
 
 #[derive(Deserialize)]
 pub struct RecordData {
@@ -22,6 +40,89 @@ pub struct RecordData {
     values: RecordValues,
 }
 
+#[derive(Deserialize)]
+pub struct RecordPatchData {
+    operations: Vec<RecordPatchOperation>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum RecordPatchOperation {
+    AddValues {
+        values: RecordValues,
+    },
+    RemoveValues {
+        values: RecordValues,
+    },
+    AddRescue {
+        values: RecordValues,
+    },
+    RemoveRescue {
+        values: RecordValues,
+    },
+    SetRegion {
+        region: RegionCode,
+        values: RecordValues,
+    },
+    UnsetRegion {
+        region: RegionCode,
+    },
+    SetTtl {
+        ttl: Option<u32>,
+    },
+}
+
+#[derive(Deserialize)]
+pub struct RecordBatchData {
+    operations: Vec<RecordBatchOperation>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum RecordBatchOperation {
+    Upsert {
+        name: RecordName,
+
+        #[serde(rename = "type")]
+        _type: RecordType,
+
+        #[serde(flatten)]
+        data: Box<RecordData>,
+    },
+    Delete {
+        name: RecordName,
+
+        #[serde(rename = "type")]
+        _type: RecordType,
+    },
+}
+
+#[derive(Deserialize)]
+pub struct RecordListQuery {
+    cursor: Option<String>,
+    limit: Option<usize>,
+
+    #[serde(rename = "type")]
+    _type: Option<RecordType>,
+
+    prefix: Option<String>,
+    flatten: Option<bool>,
+    blackhole: Option<bool>,
+    regions: Option<bool>,
+    rescue: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct ZoneImportQuery {
+    dry_run: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct ZoneListResponse {
+    name: String,
+    stored: bool,
+}
+
 #[derive(Serialize)]
 pub struct RecordGetResponse {
     #[serde(rename = "type")]
@@ -36,65 +137,355 @@ pub struct RecordGetResponse {
     values: RecordValues,
 }
 
+#[derive(Serialize)]
+pub struct RecordHistoryResponse {
+    revision: Option<u64>,
+    previous_revision: Option<u64>,
+    time: u64,
+    identity: String,
+    source: Option<IpAddr>,
+    previous: Option<RecordGetResponse>,
+}
+
+#[derive(Serialize)]
+pub struct RecordListResponse {
+    records: Vec<RecordGetResponse>,
+    cursor: Option<String>,
+}
+
+#[get("/zones")]
+pub async fn list_zones(token: web::ReqData<ConfigHTTPToken>) -> HttpResponse {
+    HttpResponse::Ok().json(
+        DNS_REGISTRY
+            .zone_names()
+            .into_iter()
+            .filter(|zone_name| {
+                ZoneName::from_str(zone_name)
+                    .map(|zone_name| token.allows(&ConfigHTTPTokenOperation::Read, &zone_name))
+                    .unwrap_or(false)
+            })
+            .map(|zone_name| ZoneListResponse {
+                stored: DNS_REGISTRY.zone_stored(&zone_name),
+                name: zone_name,
+            })
+            .collect::<Vec<ZoneListResponse>>(),
+    )
+}
+
+#[put("/zone/{zone_name}")]
+pub async fn put_zone(
+    token: web::ReqData<ConfigHTTPToken>,
+    path: web::Path<ZoneName>,
+) -> HttpResponse {
+    let zone_name = path.into_inner();
+
+    if !token.allows(&ConfigHTTPTokenOperation::Write, &zone_name) || !token.allows_all_names() {
+        return HttpResponse::Forbidden().finish();
+    }
+
+    // Zones from configuration cannot be managed through the API
+    if APP_CONF.load().dns.zone_exists(zone_name.to_str()) {
+        return HttpResponse::Conflict().finish();
+    }
+
+    APP_STORE
+        .add_zone(&zone_name)
+        .await
+        .map(|_| {
+            DNS_REGISTRY.insert(&zone_name);
+
+            HttpResponse::Ok().finish()
+        })
+        .unwrap_or(HttpResponse::ServiceUnavailable().finish())
+}
+
+#[delete("/zone/{zone_name}")]
+pub async fn delete_zone(
+    token: web::ReqData<ConfigHTTPToken>,
+    path: web::Path<ZoneName>,
+) -> HttpResponse {
+    let zone_name = path.into_inner();
+
+    if !token.allows(&ConfigHTTPTokenOperation::Write, &zone_name) || !token.allows_all_names() {
+        return HttpResponse::Forbidden().finish();
+    }
+
+    // Zones from configuration cannot be managed through the API
+    if APP_CONF.load().dns.zone_exists(zone_name.to_str()) {
+        return HttpResponse::Conflict().finish();
+    }
+
+    // Notice: zone records are removed along with the zone, thus re-creating the zone starts \
+    //   from an empty zone.
+    match APP_STORE.remove_zone(&zone_name).await {
+        Ok(true) => {
+            DNS_REGISTRY.remove(&zone_name);
+
+            HttpResponse::Ok().finish()
+        }
+        Ok(false) => HttpResponse::NotFound().finish(),
+        Err(_) => HttpResponse::ServiceUnavailable().finish(),
+    }
+}
+
+#[get("/zone/{zone_name}/records")]
+pub async fn list_zone_records(
+    token: web::ReqData<ConfigHTTPToken>,
+    path: web::Path<ZoneNameExists>,
+    query: web::Query<RecordListQuery>,
+) -> HttpResponse {
+    let zone_name = path.into_inner().into_inner();
+
+    if !token.allows(&ConfigHTTPTokenOperation::Read, &zone_name) {
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let limit = query
+        .limit
+        .unwrap_or(RECORDS_LIST_LIMIT_DEFAULT)
+        .clamp(1, RECORDS_LIST_LIMIT_MAXIMUM);
+
+    let (mut records, mut cursor, mut scanned) = (Vec::new(), query.cursor.to_owned(), 0);
+
+    // Walk the zone records index, until enough records matching filters are found
+    // Notice: the returned cursor is the last walked index entry (not the last listed record), \
+    //   so that records filtered out are not walked again on the next page. The walk is also \
+    //   capped, so that filters matching few records do not scan the whole zone at once; a \
+    //   shorter page is then returned along with its cursor.
+    loop {
+        let entries = match APP_STORE
+            .list_records(
+                &zone_name,
+                cursor.as_deref(),
+                query.prefix.as_deref(),
+                limit,
+            )
+            .await
+        {
+            Ok(entries) => entries,
+            Err(_) => return HttpResponse::ServiceUnavailable().finish(),
+        };
+
+        let is_last_page = entries.len() < limit;
+
+        scanned += entries.len();
+
+        // Notice: records out of token names are skipped, as if they did not exist.
+        let (mut candidates, mut keys) = (Vec::new(), Vec::new());
+
+        for (entry, key) in entries {
+            cursor = Some(entry.to_owned());
+
+            if let Some((record_name, record_type)) = key {
+                if query._type.as_ref().map(|kind| kind != &record_type) != Some(true)
+                    && token.allows_name(&record_name)
+                {
+                    candidates.push(entry);
+                    keys.push((record_name, record_type));
+                }
+            }
+        }
+
+        let fetched = match APP_STORE.get_records(&zone_name, &keys).await {
+            Ok(fetched) => fetched,
+            Err(_) => return HttpResponse::ServiceUnavailable().finish(),
+        };
+
+        for (entry, record) in candidates.into_iter().zip(fetched) {
+            let record = match record {
+                Some(record) => record,
+                None => continue,
+            };
+
+            let is_matching = [
+                (query.flatten, record.flatten == Some(true)),
+                (query.blackhole, record.blackhole.is_some()),
+                (query.regions, record.regions.is_some()),
+                (query.rescue, record.rescue.is_some()),
+            ]
+            .iter()
+            .all(|(filter, value)| filter.map(|filter| filter == *value).unwrap_or(true));
+
+            if is_matching {
+                records.push(RecordGetResponse {
+                    _type: record.kind,
+                    name: record.name,
+                    ttl: record.ttl,
+                    flatten: record.flatten,
+                    blackhole: record.blackhole,
+                    regions: record.regions,
+                    rescue: record.rescue,
+                    values: record.values,
+                });
+
+                // Notice: the cursor is the index entry of the last listed record, as next \
+                //   entries were not listed yet.
+                if records.len() >= limit {
+                    return HttpResponse::Ok().json(RecordListResponse {
+                        records,
+                        cursor: Some(entry),
+                    });
+                }
+            }
+        }
+
+        if is_last_page {
+            return HttpResponse::Ok().json(RecordListResponse {
+                records,
+                cursor: None,
+            });
+        }
+
+        if scanned >= RECORDS_LIST_SCAN_MAXIMUM {
+            return HttpResponse::Ok().json(RecordListResponse { records, cursor });
+        }
+    }
+}
+
+#[get("/zone/{zone_name}/export")]
+pub async fn export_zone(
+    token: web::ReqData<ConfigHTTPToken>,
+    path: web::Path<ZoneNameExists>,
+) -> HttpResponse {
+    let zone_name = path.into_inner().into_inner();
+
+    if !token.allows(&ConfigHTTPTokenOperation::Read, &zone_name) || !token.allows_all_names() {
+        return HttpResponse::Forbidden().finish();
+    }
+
+    match DNSZoneFile::export(&zone_name).await {
+        Ok(zone_file) => HttpResponse::Ok()
+            .content_type(ZONE_FILE_MIME)
+            .body(zone_file),
+        Err(_) => HttpResponse::ServiceUnavailable().finish(),
+    }
+}
+
+#[post("/zone/{zone_name}/import")]
+pub async fn import_zone(
+    request: HttpRequest,
+    token: web::ReqData<ConfigHTTPToken>,
+    path: web::Path<ZoneNameExists>,
+    query: web::Query<ZoneImportQuery>,
+
+    data: String,
+) -> HttpResponse {
+    let zone_name = path.into_inner().into_inner();
+
+    if !token.allows(&ConfigHTTPTokenOperation::Write, &zone_name) || !token.allows_all_names() {
+        return HttpResponse::Forbidden().finish();
+    }
+
+    match DNSZoneFile::import(
+        &zone_name,
+        &data,
+        query.dry_run.unwrap_or(false),
+        &request_author(&request, &token),
+    )
+    .await
+    {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(DNSZoneFileError::Invalid(reason)) => {
+            info!("rejected zone file import: {}", reason);
+
+            HttpResponse::BadRequest().finish()
+        }
+        Err(DNSZoneFileError::Store) => HttpResponse::ServiceUnavailable().finish(),
+    }
+}
+
 #[head("/zone/{zone_name}/record/{record_name}/{record_type}")]
 pub async fn head_zone_record(
+    token: web::ReqData<ConfigHTTPToken>,
     path: web::Path<(ZoneNameExists, RecordName, RecordType)>,
 ) -> HttpResponse {
     let (zone_name, record_name, record_type) = path.into_inner();
+    let zone_name = zone_name.into_inner();
+
+    if !token.allows(&ConfigHTTPTokenOperation::Read, &zone_name)
+        || !token.allows_name(&record_name)
+    {
+        return HttpResponse::Forbidden().finish();
+    }
 
     APP_STORE
         .get(
-            &zone_name.into_inner(),
+            &zone_name,
             &record_name,
             &record_type,
             StoreAccessOrigin::Internal,
         )
         .await
-        .map(|_| HttpResponse::Ok().finish())
+        .map(|record| {
+            HttpResponse::Ok()
+                .insert_header(record_etag(&record))
+                .finish()
+        })
         .unwrap_or(HttpResponse::NotFound().finish())
 }
 
 #[get("/zone/{zone_name}/record/{record_name}/{record_type}")]
 pub async fn get_zone_record(
+    token: web::ReqData<ConfigHTTPToken>,
     path: web::Path<(ZoneNameExists, RecordName, RecordType)>,
 ) -> HttpResponse {
     let (zone_name, record_name, record_type) = path.into_inner();
+    let zone_name = zone_name.into_inner();
+
+    if !token.allows(&ConfigHTTPTokenOperation::Read, &zone_name)
+        || !token.allows_name(&record_name)
+    {
+        return HttpResponse::Forbidden().finish();
+    }
 
     APP_STORE
         .get(
-            &zone_name.into_inner(),
+            &zone_name,
             &record_name,
             &record_type,
             StoreAccessOrigin::Internal,
         )
         .await
         .map(|record| {
-            HttpResponse::Ok().json(RecordGetResponse {
-                _type: record.kind,
-                name: record.name,
-                ttl: record.ttl,
-                flatten: record.flatten,
-                blackhole: record.blackhole,
-                regions: record.regions,
-                rescue: record.rescue,
-                values: record.values,
-            })
+            HttpResponse::Ok()
+                .insert_header(record_etag(&record))
+                .json(RecordGetResponse {
+                    _type: record.kind,
+                    name: record.name,
+                    ttl: record.ttl,
+                    flatten: record.flatten,
+                    blackhole: record.blackhole,
+                    regions: record.regions,
+                    rescue: record.rescue,
+                    values: record.values,
+                })
         })
         .unwrap_or(HttpResponse::NotFound().finish())
 }
 
 #[put("/zone/{zone_name}/record/{record_name}/{record_type}")]
 pub async fn put_zone_record(
+    request: HttpRequest,
+    token: web::ReqData<ConfigHTTPToken>,
     path: web::Path<(ZoneNameExists, RecordName, RecordType)>,
+    if_match: Option<web::Header<IfMatch>>,
+    if_none_match: Option<web::Header<IfNoneMatch>>,
 
     data: web::Json<RecordData>,
 ) -> HttpResponse {
     let (zone_name, record_name, record_type) = path.into_inner();
+    let zone_name = zone_name.into_inner();
 
-    APP_STORE
-        .set(
-            &zone_name.into_inner(),
-            StoreRecord {
+    if !token.allows(&ConfigHTTPTokenOperation::Write, &zone_name)
+        || !token.allows_name(&record_name)
+    {
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let result = APP_STORE
+        .apply_if(
+            &zone_name,
+            vec![StoreOperation::Set(Box::new(StoreRecord {
                 kind: record_type,
                 name: record_name,
                 ttl: data.ttl,
@@ -103,70 +494,485 @@ pub async fn put_zone_record(
                 regions: data.regions.to_owned(),
                 rescue: data.rescue.to_owned(),
                 values: data.values.to_owned(),
+                revision: None,
+            }))],
+            &record_condition(if_match, if_none_match),
+            &request_author(&request, &token),
+        )
+        .await;
+
+    match result {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(StoreError::PreconditionFailed) => HttpResponse::PreconditionFailed().finish(),
+        Err(_) => HttpResponse::ServiceUnavailable().finish(),
+    }
+}
+
+#[patch("/zone/{zone_name}/record/{record_name}/{record_type}")]
+pub async fn patch_zone_record(
+    request: HttpRequest,
+    token: web::ReqData<ConfigHTTPToken>,
+    path: web::Path<(ZoneNameExists, RecordName, RecordType)>,
+    if_match: Option<web::Header<IfMatch>>,
+    if_none_match: Option<web::Header<IfNoneMatch>>,
+
+    data: web::Json<RecordPatchData>,
+) -> HttpResponse {
+    let (zone_name, record_name, record_type) = path.into_inner();
+    let zone_name = zone_name.into_inner();
+
+    if !token.allows(&ConfigHTTPTokenOperation::Write, &zone_name)
+        || !token.allows_name(&record_name)
+    {
+        return HttpResponse::Forbidden().finish();
+    }
+
+    // Validate all added values, as none of the operations must be applied if any of them is \
+    //   invalid
+    let is_valid = !data.operations.is_empty()
+        && data.operations.iter().all(|operation| match operation {
+            RecordPatchOperation::AddValues { values }
+            | RecordPatchOperation::AddRescue { values }
+            | RecordPatchOperation::SetRegion { values, .. } => {
+                !values.is_empty()
+                    && values
+                        .iter()
+                        .all(|value| value.to_hickory(&record_type).is_ok())
+            }
+            _ => true,
+        });
+
+    if !is_valid {
+        return HttpResponse::BadRequest().finish();
+    }
+
+    let result = APP_STORE
+        .modify(
+            &zone_name,
+            &record_name,
+            &record_type,
+            &record_condition(if_match, if_none_match),
+            &request_author(&request, &token),
+            |mut record| {
+                for operation in data.operations.iter() {
+                    match operation {
+                        RecordPatchOperation::AddValues { values } => record.values.add(values),
+                        RecordPatchOperation::RemoveValues { values } => {
+                            record.values.remove(values)
+                        }
+                        RecordPatchOperation::AddRescue { values } => record
+                            .rescue
+                            .get_or_insert_with(RecordValues::new)
+                            .add(values),
+                        RecordPatchOperation::RemoveRescue { values } => {
+                            if let Some(ref mut rescue) = record.rescue {
+                                rescue.remove(values);
+                            }
+                        }
+                        RecordPatchOperation::SetRegion { region, values } => {
+                            *record
+                                .regions
+                                .get_or_insert_with(RecordRegions::default)
+                                .region_mut(region) = Some(values.to_owned());
+                        }
+                        RecordPatchOperation::UnsetRegion { region } => {
+                            if let Some(ref mut regions) = record.regions {
+                                *regions.region_mut(region) = None;
+                            }
+                        }
+                        RecordPatchOperation::SetTtl { ttl } => record.ttl = *ttl,
+                    }
+                }
+
+                // Drop emptied options, and refuse to leave the record without any value
+                record.rescue = record.rescue.filter(|rescue| !rescue.is_empty());
+                record.regions = record.regions.filter(|regions| regions.has_items());
+
+                if record.values.is_empty() {
+                    None
+                } else {
+                    Some(record)
+                }
             },
         )
-        .await
-        .map(|_| HttpResponse::Ok().finish())
-        .unwrap_or(HttpResponse::ServiceUnavailable().finish())
+        .await;
+
+    match result {
+        Ok(Some(record)) => {
+            HttpResponse::Ok()
+                .insert_header(record_etag(&record))
+                .json(RecordGetResponse {
+                    _type: record.kind,
+                    name: record.name,
+                    ttl: record.ttl,
+                    flatten: record.flatten,
+                    blackhole: record.blackhole,
+                    regions: record.regions,
+                    rescue: record.rescue,
+                    values: record.values,
+                })
+        }
+        Ok(None) => HttpResponse::Conflict().finish(),
+        Err(StoreError::NotFound) => HttpResponse::NotFound().finish(),
+        Err(StoreError::PreconditionFailed) => HttpResponse::PreconditionFailed().finish(),
+        Err(_) => HttpResponse::ServiceUnavailable().finish(),
+    }
 }
 
 #[delete("/zone/{zone_name}/record/{record_name}/{record_type}")]
 pub async fn delete_zone_record(
+    request: HttpRequest,
+    token: web::ReqData<ConfigHTTPToken>,
     path: web::Path<(ZoneNameExists, RecordName, RecordType)>,
+    if_match: Option<web::Header<IfMatch>>,
+    if_none_match: Option<web::Header<IfNoneMatch>>,
 ) -> HttpResponse {
     let (zone_name, record_name, record_type) = path.into_inner();
+    let zone_name = zone_name.into_inner();
+
+    if !token.allows(&ConfigHTTPTokenOperation::Write, &zone_name)
+        || !token.allows_name(&record_name)
+    {
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let result = APP_STORE
+        .apply_if(
+            &zone_name,
+            vec![StoreOperation::Remove(record_name, record_type)],
+            &record_condition(if_match, if_none_match),
+            &request_author(&request, &token),
+        )
+        .await;
+
+    match result {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(StoreError::PreconditionFailed) => HttpResponse::PreconditionFailed().finish(),
+        Err(_) => HttpResponse::ServiceUnavailable().finish(),
+    }
+}
+
+#[post("/zone/{zone_name}/records/batch")]
+pub async fn batch_zone_records(
+    request: HttpRequest,
+    token: web::ReqData<ConfigHTTPToken>,
+    path: web::Path<ZoneNameExists>,
+
+    data: web::Json<RecordBatchData>,
+) -> HttpResponse {
+    let zone_name = path.into_inner().into_inner();
+
+    if !token.allows(&ConfigHTTPTokenOperation::Write, &zone_name) {
+        return HttpResponse::Forbidden().finish();
+    }
+
+    if data.operations.is_empty() || data.operations.len() > RECORDS_BATCH_OPERATIONS_MAXIMUM {
+        return HttpResponse::BadRequest().finish();
+    }
+
+    let mut operations: Vec<StoreOperation> = Vec::new();
+
+    for operation in data.into_inner().operations {
+        let operation = match operation {
+            RecordBatchOperation::Upsert { name, _type, data } => {
+                let record = StoreRecord {
+                    kind: _type,
+                    name,
+                    ttl: data.ttl,
+                    flatten: data.flatten,
+                    blackhole: data.blackhole,
+                    regions: data.regions,
+                    rescue: data.rescue,
+                    values: data.values,
+                    revision: None,
+                };
+
+                // Validate all record values, as none of the operations must be applied if any \
+                //   of them is invalid
+                let is_valid = !record.values.is_empty()
+                    && record
+                        .list_record_values()
+                        .into_iter()
+                        .chain(record.rescue.iter().flat_map(|rescue| rescue.iter()))
+                        .all(|value| value.to_hickory(&record.kind).is_ok());
+
+                if !is_valid {
+                    return HttpResponse::BadRequest().finish();
+                }
+
+                StoreOperation::Set(Box::new(record))
+            }
+            RecordBatchOperation::Delete { name, _type } => StoreOperation::Remove(name, _type),
+        };
+
+        // All records must be within token names, as none of the operations must be applied if \
+        //   any of them is refused
+        if !token.allows_name(operation.key().0) {
+            return HttpResponse::Forbidden().finish();
+        }
+
+        // A record can only be operated on once per batch
+        if operations
+            .iter()
+            .any(|other_operation| other_operation.key() == operation.key())
+        {
+            return HttpResponse::BadRequest().finish();
+        }
+
+        operations.push(operation);
+    }
 
     APP_STORE
-        .remove(&zone_name.into_inner(), &record_name, &record_type)
+        .apply(&zone_name, operations, &request_author(&request, &token))
         .await
         .map(|_| HttpResponse::Ok().finish())
         .unwrap_or(HttpResponse::ServiceUnavailable().finish())
 }
 
+#[get("/zone/{zone_name}/record/{record_name}/{record_type}/history")]
+pub async fn get_zone_record_history(
+    token: web::ReqData<ConfigHTTPToken>,
+    path: web::Path<(ZoneNameExists, RecordName, RecordType)>,
+) -> HttpResponse {
+    let (zone_name, record_name, record_type) = path.into_inner();
+    let zone_name = zone_name.into_inner();
+
+    if !token.allows(&ConfigHTTPTokenOperation::Read, &zone_name)
+        || !token.allows_name(&record_name)
+    {
+        return HttpResponse::Forbidden().finish();
+    }
+
+    APP_STORE
+        .list_history(&zone_name, &record_name, &record_type)
+        .await
+        .map(|history| {
+            HttpResponse::Ok().json(
+                history
+                    .into_iter()
+                    .map(|entry| RecordHistoryResponse {
+                        revision: entry.revision,
+                        previous_revision: entry
+                            .previous
+                            .as_ref()
+                            .and_then(|previous| previous.revision),
+                        time: entry.time,
+                        identity: entry.identity,
+                        source: entry.source,
+                        previous: entry.previous.map(|previous| RecordGetResponse {
+                            _type: previous.kind,
+                            name: previous.name,
+                            ttl: previous.ttl,
+                            flatten: previous.flatten,
+                            blackhole: previous.blackhole,
+                            regions: previous.regions,
+                            rescue: previous.rescue,
+                            values: previous.values,
+                        }),
+                    })
+                    .collect::<Vec<RecordHistoryResponse>>(),
+            )
+        })
+        .unwrap_or(HttpResponse::ServiceUnavailable().finish())
+}
+
+#[post("/zone/{zone_name}/record/{record_name}/{record_type}/history/{revision}/revert")]
+pub async fn revert_zone_record(
+    request: HttpRequest,
+    token: web::ReqData<ConfigHTTPToken>,
+    path: web::Path<(ZoneNameExists, RecordName, RecordType, u64)>,
+    if_match: Option<web::Header<IfMatch>>,
+    if_none_match: Option<web::Header<IfNoneMatch>>,
+) -> HttpResponse {
+    let (zone_name, record_name, record_type, revision) = path.into_inner();
+    let zone_name = zone_name.into_inner();
+
+    if !token.allows(&ConfigHTTPTokenOperation::Write, &zone_name)
+        || !token.allows_name(&record_name)
+    {
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let history = match APP_STORE
+        .list_history(&zone_name, &record_name, &record_type)
+        .await
+    {
+        Ok(history) => history,
+        Err(_) => return HttpResponse::ServiceUnavailable().finish(),
+    };
+
+    // Notice: revisions are consecutive, thus a revision lower than the last one that is not \
+    //   found in history was trimmed from history (as opposed to a revision that never existed).
+    let (current_revision, last_revision) = match history.first() {
+        Some(entry) => (
+            entry.revision,
+            entry
+                .revision
+                .or(entry
+                    .previous
+                    .as_ref()
+                    .and_then(|previous| previous.revision))
+                .unwrap_or(0),
+        ),
+        None => return HttpResponse::NotFound().finish(),
+    };
+
+    if revision == 0 || revision > last_revision {
+        return HttpResponse::NotFound().finish();
+    }
+
+    if current_revision == Some(revision) {
+        return HttpResponse::Conflict().json(CatcherResponse {
+            error: "conflict",
+            message: Some("revision is the current revision of the record"),
+        });
+    }
+
+    // Restore the record as it was before the change that replaced the chosen revision
+    let record = match history
+        .into_iter()
+        .filter_map(|entry| entry.previous)
+        .find(|previous| previous.revision == Some(revision))
+    {
+        Some(record) => record,
+        None => {
+            return HttpResponse::Conflict().json(CatcherResponse {
+                error: "conflict",
+                message: Some("revision is not in history anymore"),
+            })
+        }
+    };
+
+    let result = APP_STORE
+        .apply_if(
+            &zone_name,
+            vec![StoreOperation::Set(Box::new(StoreRecord {
+                revision: None,
+                ..record
+            }))],
+            &record_condition(if_match, if_none_match),
+            &request_author(&request, &token),
+        )
+        .await;
+
+    match result {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(StoreError::PreconditionFailed) => HttpResponse::PreconditionFailed().finish(),
+        Err(_) => HttpResponse::ServiceUnavailable().finish(),
+    }
+}
+
 #[get("/zone/{zone_name}/metrics/{metrics_timespan}/query/types")]
 pub async fn get_metrics_query_types(
+    token: web::ReqData<ConfigHTTPToken>,
     path: web::Path<(ZoneNameExists, MetricsTimespan)>,
 ) -> HttpResponse {
     let (zone_name, metrics_timespan) = path.into_inner();
+    let zone_name = zone_name.into_inner();
+
+    if !token.allows(&ConfigHTTPTokenOperation::Metrics, &zone_name) {
+        return HttpResponse::Forbidden().finish();
+    }
 
     METRICS_STORE
-        .aggregate(
-            &zone_name.into_inner(),
-            MetricsType::QueryType,
-            metrics_timespan,
-        )
+        .aggregate(&zone_name, MetricsType::QueryType, metrics_timespan)
         .map(|aggregated| HttpResponse::Ok().json(aggregated))
         .unwrap_or(HttpResponse::NotFound().finish())
 }
 
 #[get("/zone/{zone_name}/metrics/{metrics_timespan}/query/origins")]
 pub async fn get_metrics_query_origins(
+    token: web::ReqData<ConfigHTTPToken>,
     path: web::Path<(ZoneNameExists, MetricsTimespan)>,
 ) -> HttpResponse {
     let (zone_name, metrics_timespan) = path.into_inner();
+    let zone_name = zone_name.into_inner();
+
+    if !token.allows(&ConfigHTTPTokenOperation::Metrics, &zone_name) {
+        return HttpResponse::Forbidden().finish();
+    }
 
     METRICS_STORE
-        .aggregate(
-            &zone_name.into_inner(),
-            MetricsType::QueryOrigin,
-            metrics_timespan,
-        )
+        .aggregate(&zone_name, MetricsType::QueryOrigin, metrics_timespan)
         .map(|aggregated| HttpResponse::Ok().json(aggregated))
         .unwrap_or(HttpResponse::NotFound().finish())
 }
 
 #[get("/zone/{zone_name}/metrics/{metrics_timespan}/answer/codes")]
 pub async fn get_metrics_answer_codes(
+    token: web::ReqData<ConfigHTTPToken>,
     path: web::Path<(ZoneNameExists, MetricsTimespan)>,
 ) -> HttpResponse {
     let (zone_name, metrics_timespan) = path.into_inner();
+    let zone_name = zone_name.into_inner();
+
+    if !token.allows(&ConfigHTTPTokenOperation::Metrics, &zone_name) {
+        return HttpResponse::Forbidden().finish();
+    }
 
     METRICS_STORE
-        .aggregate(
-            &zone_name.into_inner(),
-            MetricsType::AnswerCode,
-            metrics_timespan,
-        )
+        .aggregate(&zone_name, MetricsType::AnswerCode, metrics_timespan)
+        .map(|aggregated| HttpResponse::Ok().json(aggregated))
+        .unwrap_or(HttpResponse::NotFound().finish())
+}
+
+#[get("/zone/{zone_name}/metrics/{metrics_timespan}/answer/ratelimits")]
+pub async fn get_metrics_answer_rate_limits(
+    token: web::ReqData<ConfigHTTPToken>,
+    path: web::Path<(ZoneNameExists, MetricsTimespan)>,
+) -> HttpResponse {
+    let (zone_name, metrics_timespan) = path.into_inner();
+    let zone_name = zone_name.into_inner();
+
+    if !token.allows(&ConfigHTTPTokenOperation::Metrics, &zone_name) {
+        return HttpResponse::Forbidden().finish();
+    }
+
+    METRICS_STORE
+        .aggregate(&zone_name, MetricsType::AnswerRateLimit, metrics_timespan)
         .map(|aggregated| HttpResponse::Ok().json(aggregated))
         .unwrap_or(HttpResponse::NotFound().finish())
 }
+
+fn record_etag(record: &StoreRecord) -> ETag {
+    ETag(EntityTag::new_strong(
+        record.revision.unwrap_or(0).to_string(),
+    ))
+}
+
+fn request_author(request: &HttpRequest, token: &ConfigHTTPToken) -> StoreAuthor {
+    StoreAuthor {
+        identity: format!("http:{}", token.name),
+        source: request.peer_addr().map(|address| address.ip()),
+    }
+}
+
+fn record_condition(
+    if_match: Option<web::Header<IfMatch>>,
+    if_none_match: Option<web::Header<IfNoneMatch>>,
+) -> StoreCondition {
+    // Notice: 'If-Match' uses the strong comparison, thus weak tags never match, while \
+    //   'If-None-Match' uses the weak comparison (as per RFC 9110). Absent headers are parsed \
+    //   as empty lists of tags, which do not set any condition.
+    StoreCondition {
+        if_match: if_match.and_then(|if_match| match if_match.into_inner() {
+            IfMatch::Any => Some(StoreConditionRevisions::Any),
+            IfMatch::Items(tags) if tags.is_empty() => None,
+            IfMatch::Items(tags) => Some(StoreConditionRevisions::List(
+                tags.iter()
+                    .filter(|tag| !tag.weak)
+                    .filter_map(|tag| tag.tag().parse().ok())
+                    .collect(),
+            )),
+        }),
+        if_none_match: if_none_match.and_then(|if_none_match| match if_none_match.into_inner() {
+            IfNoneMatch::Any => Some(StoreConditionRevisions::Any),
+            IfNoneMatch::Items(tags) if tags.is_empty() => None,
+            IfNoneMatch::Items(tags) => Some(StoreConditionRevisions::List(
+                tags.iter()
+                    .filter_map(|tag| tag.tag().parse().ok())
+                    .collect(),
+            )),
+        }),
+    }
+}
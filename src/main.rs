@@ -18,38 +18,58 @@ mod http;
 mod store;
 
 use std::ops::Deref;
+use std::process;
 use std::str::FromStr;
 use std::thread;
 use std::time::Duration;
 
-use clap::{Arg, Command};
+use arc_swap::ArcSwap;
+use clap::{Arg, ArgAction, Command};
 use log::LevelFilter;
 
 use config::config::Config;
 use config::logger::ConfigLogger;
 use config::reader::ConfigReader;
+use config::reload::ConfigReloadBuilder;
 use dns::flatten::{DNSFlattenBootstrapBuilder, DNSFlattenMaintainBuilder};
 use dns::health::DNSHealthBuilder;
+use dns::https::DNSHTTPSListenBuilder;
 use dns::listen::DNSListenBuilder;
 use dns::metrics::DNSMetricsTickBuilder;
+use dns::registry::DNSRegistrySyncBuilder;
+use dns::secondary::DNSSecondaryTransferBuilder;
+use dns::zonefile::DNSZoneFileImportBuilder;
 use geo::locate::DB_READER;
 use geo::updater::GeoUpdaterBuilder;
 use http::listen::HTTPListenBuilder;
 use store::flush::StoreFlushBuilder;
+use store::migrate::StoreMigrateBuilder;
 use store::store::{Store, StoreBuilder};
 
 struct AppArgs {
     config: String,
+    import: Option<AppArgsImport>,
+}
+
+struct AppArgsImport {
+    zone: String,
+    file: String,
+    dry_run: bool,
 }
 
 pub static THREAD_NAME_DNS: &'static str = "constellation-dns";
+pub static THREAD_NAME_DNS_HTTPS: &str = "constellation-dns-https";
 pub static THREAD_NAME_HTTP: &'static str = "constellation-http";
 pub static THREAD_NAME_STORE_FLUSH: &'static str = "constellation-store-flush";
+pub static THREAD_NAME_STORE_MIGRATE: &str = "constellation-store-migrate";
 pub static THREAD_NAME_DNS_METRICS: &'static str = "constellation-dns-metrics";
 pub static THREAD_NAME_DNS_HEALTH: &'static str = "constellation-dns-health";
+pub static THREAD_NAME_DNS_SECONDARY: &str = "constellation-dns-secondary";
+pub static THREAD_NAME_DNS_REGISTRY: &str = "constellation-dns-registry";
 pub static THREAD_NAME_DNS_FLATTEN_BOOTSTRAP: &'static str = "constellation-dns-flatten-bootstrap";
 pub static THREAD_NAME_DNS_FLATTEN_MAINTAIN: &'static str = "constellation-dns-flatten-maintain";
 pub static THREAD_NAME_GEO_UPDATER: &'static str = "constellation-geo-updater";
+pub static THREAD_NAME_CONFIG_RELOAD: &str = "constellation-config-reload";
 
 macro_rules! gen_spawn_managed {
     ($name:expr, $method:ident, $thread_name:ident, $managed_fn:expr) => {
@@ -82,7 +102,7 @@ macro_rules! gen_spawn_managed {
 
 lazy_static! {
     static ref APP_ARGS: AppArgs = make_app_args();
-    static ref APP_CONF: Config = ConfigReader::make();
+    static ref APP_CONF: ArcSwap<Config> = ArcSwap::from_pointee(ConfigReader::make());
     static ref APP_STORE: Store = StoreBuilder::new();
 }
 
@@ -92,6 +112,12 @@ gen_spawn_managed!(
     THREAD_NAME_DNS,
     DNSListenBuilder::new().run()
 );
+gen_spawn_managed!(
+    "dns_https",
+    spawn_dns_https,
+    THREAD_NAME_DNS_HTTPS,
+    DNSHTTPSListenBuilder::new().run()
+);
 gen_spawn_managed!(
     "http",
     spawn_http,
@@ -104,6 +130,13 @@ gen_spawn_managed!(
     THREAD_NAME_STORE_FLUSH,
     StoreFlushBuilder::new().run()
 );
+
+gen_spawn_managed!(
+    "store_migrate",
+    spawn_store_migrate,
+    THREAD_NAME_STORE_MIGRATE,
+    StoreMigrateBuilder::new().run()
+);
 gen_spawn_managed!(
     "dns_metrics",
     spawn_dns_metrics,
@@ -116,6 +149,19 @@ gen_spawn_managed!(
     THREAD_NAME_DNS_HEALTH,
     DNSHealthBuilder::new().run()
 );
+gen_spawn_managed!(
+    "dns_secondary",
+    spawn_dns_secondary,
+    THREAD_NAME_DNS_SECONDARY,
+    DNSSecondaryTransferBuilder::new().run()
+);
+gen_spawn_managed!(
+    "dns_registry",
+    spawn_dns_registry,
+    THREAD_NAME_DNS_REGISTRY,
+    DNSRegistrySyncBuilder::new().run()
+);
+
 gen_spawn_managed!(
     "dns_flatten_maintain",
     spawn_dns_flatten_maintain,
@@ -134,6 +180,12 @@ gen_spawn_managed!(
     THREAD_NAME_GEO_UPDATER,
     GeoUpdaterBuilder::new().run()
 );
+gen_spawn_managed!(
+    "config_reload",
+    spawn_config_reload,
+    THREAD_NAME_CONFIG_RELOAD,
+    ConfigReloadBuilder::new().run()
+);
 
 fn make_app_args() -> AppArgs {
     let matches = Command::new(clap::crate_name!())
@@ -147,6 +199,30 @@ fn make_app_args() -> AppArgs {
                 .help("Path to configuration file")
                 .default_value("./config.cfg"),
         )
+        .subcommand(
+            Command::new("import")
+                .about("Imports records from a zone file into a zone, then exits")
+                .arg(
+                    Arg::new("zone")
+                        .short('z')
+                        .long("zone")
+                        .help("Name of the zone to import records into")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("file")
+                        .short('f')
+                        .long("file")
+                        .help("Path to zone file (RFC 1035 format)")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .help("Report changes without writing records")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
         .get_matches();
 
     // Generate owned app arguments
@@ -155,6 +231,19 @@ fn make_app_args() -> AppArgs {
             .get_one::<String>("config")
             .expect("invalid config value")
             .to_owned(),
+        import: matches
+            .subcommand_matches("import")
+            .map(|import_matches| AppArgsImport {
+                zone: import_matches
+                    .get_one::<String>("zone")
+                    .expect("invalid zone value")
+                    .to_owned(),
+                file: import_matches
+                    .get_one::<String>("file")
+                    .expect("invalid file value")
+                    .to_owned(),
+                dry_run: import_matches.get_flag("dry-run"),
+            }),
     }
 }
 
@@ -166,19 +255,11 @@ fn ensure_states() {
         APP_STORE.deref(),
         DB_READER.deref(),
     );
-
-    // Ensure that there is at least a DNS flatten resolver configured
-    assert_eq!(
-        APP_CONF.dns.flatten.resolvers.is_empty(),
-        false,
-        "dns flatten resolver list is empty, please provide at least a resolver in [{}]",
-        "dns.flatten.resolvers"
-    );
 }
 
 fn main() {
     let _logger = ConfigLogger::init(
-        LevelFilter::from_str(&APP_CONF.server.log_level).expect("invalid log level"),
+        LevelFilter::from_str(&APP_CONF.load().server.log_level).expect("invalid log level"),
     );
 
     info!("starting up");
@@ -186,29 +267,58 @@ fn main() {
     // Ensure all states are bound
     ensure_states();
 
+    // Run zone file import? (one-shot command, servers are not started)
+    if let Some(ref import) = APP_ARGS.import {
+        let result =
+            DNSZoneFileImportBuilder::new(&import.zone, &import.file, import.dry_run).run();
+
+        process::exit(if result.is_ok() { 0 } else { 1 });
+    }
+
     // Spawn store flush
     thread::spawn(spawn_store_flush);
 
+    // Run store migrations (foreground thread)
+    // Notice: this blocks until migrations complete, as DNS lookups rely on the indexes they \
+    //   build, and would otherwise answer NXDOMAIN for existing names until they are built.
+    spawn_store_migrate();
+
     // Spawn DNS metrics
     thread::spawn(spawn_dns_metrics);
 
+    // Spawn DNS zone registry sync
+    thread::spawn(spawn_dns_registry);
+
     // Spawn DNS flattener
     thread::spawn(spawn_dns_flatten_bootstrap);
     thread::spawn(spawn_dns_flatten_maintain);
 
-    // Spawn DNS health checker? (background thread)
-    if APP_CONF.dns.health.check_enable == true {
-        thread::spawn(spawn_dns_health);
-    }
+    // Spawn DNS health checker (background thread)
+    // Notice: this is spawned even if health checks are disabled, as they can get enabled \
+    //   upon configuration reload.
+    thread::spawn(spawn_dns_health);
+
+    // Spawn DNS secondary zones transfer (background thread)
+    // Notice: this is spawned even if there are no secondary zones, as they can get added \
+    //   upon configuration reload.
+    thread::spawn(spawn_dns_secondary);
+
+    // Spawn configuration reloader (background thread)
+    thread::spawn(spawn_config_reload);
 
     // Spawn geo updater? (background thread)
-    if APP_CONF.geo.update_enable == true {
+    if APP_CONF.load().geo.update_enable {
         thread::spawn(spawn_geo_updater);
     }
 
     // Run DNS server (background thread)
     thread::spawn(spawn_dns);
 
+    // Run DNS-over-HTTPS server? (background thread)
+    if !APP_CONF.load().dns.https.inets.is_empty() {
+        thread::spawn(spawn_dns_https);
+    }
+
     // Spawn HTTP server (foreground thread)
     spawn_http();
 
@@ -9,6 +9,7 @@ use std::sync::RwLock;
 use std::time::SystemTime;
 
 use super::store::StoreRecord;
+use crate::dns::record::RecordType;
 use crate::APP_CONF;
 use crate::APP_STORE;
 
@@ -25,11 +26,25 @@ pub struct StoreCache {
 pub struct StoreCacheFlush;
 
 struct StoreCacheEntry {
-    record: Option<StoreRecord>,
+    value: StoreCacheValue,
     refreshed_at: SystemTime,
     accessed_at: SystemTime,
 }
 
+#[derive(Clone)]
+enum StoreCacheValue {
+    Record(Box<Option<StoreRecord>>),
+    Descendants(bool),
+    Types(Vec<RecordType>),
+}
+
+#[derive(Clone, Copy)]
+enum StoreCacheKind {
+    Record,
+    Descendants,
+    Types,
+}
+
 impl StoreCacheBuilder {
     fn new() -> StoreCache {
         StoreCache {
@@ -40,21 +55,37 @@ impl StoreCacheBuilder {
 
 impl StoreCache {
     pub fn get(&self, store_key: &str) -> Result<Option<StoreRecord>, ()> {
-        let mut cache_write = self.cache.write().unwrap();
-
         debug!("store cache get on key: {}", store_key);
 
-        if let Some(ref mut store_record) = cache_write.get_mut(store_key) {
-            debug!("store cache get got records for key: {}", store_key);
+        match self.get_value(store_key) {
+            Ok(StoreCacheValue::Record(record)) => {
+                debug!("store cache get got records for key: {}", store_key);
 
-            // Bump last cache access time
-            store_record.accessed_at = SystemTime::now();
+                Ok(*record)
+            }
+            _ => {
+                debug!("store cache get did not get records for key: {}", store_key);
 
-            Ok(store_record.record.clone())
-        } else {
-            debug!("store cache get did not get records for key: {}", store_key);
+                Err(())
+            }
+        }
+    }
 
-            Err(())
+    pub fn get_descendants(&self, descendants_key: &str) -> Result<bool, ()> {
+        debug!("store cache get descendants on key: {}", descendants_key);
+
+        match self.get_value(descendants_key) {
+            Ok(StoreCacheValue::Descendants(has_descendants)) => Ok(has_descendants),
+            _ => Err(()),
+        }
+    }
+
+    pub fn get_types(&self, types_key: &str) -> Result<Vec<RecordType>, ()> {
+        debug!("store cache get types on key: {}", types_key);
+
+        match self.get_value(types_key) {
+            Ok(StoreCacheValue::Types(record_types)) => Ok(record_types),
+            _ => Err(()),
         }
     }
 
@@ -64,16 +95,41 @@ impl StoreCache {
         store_record: Option<StoreRecord>,
         accessed_at: Option<SystemTime>,
     ) {
-        let mut cache_write = self.cache.write().unwrap();
-
         debug!("store cache push on key: {}", store_key);
 
-        cache_write.insert(
-            store_key.to_string(),
-            StoreCacheEntry::new(store_record, accessed_at),
+        self.push_value(
+            store_key,
+            StoreCacheValue::Record(Box::new(store_record)),
+            accessed_at,
+        );
+    }
+
+    pub fn push_descendants(
+        &self,
+        descendants_key: &str,
+        has_descendants: bool,
+        accessed_at: Option<SystemTime>,
+    ) {
+        debug!("store cache push descendants on key: {}", descendants_key);
+
+        self.push_value(
+            descendants_key,
+            StoreCacheValue::Descendants(has_descendants),
+            accessed_at,
         );
     }
 
+    pub fn push_types(
+        &self,
+        types_key: &str,
+        record_types: Vec<RecordType>,
+        accessed_at: Option<SystemTime>,
+    ) {
+        debug!("store cache push types on key: {}", types_key);
+
+        self.push_value(types_key, StoreCacheValue::Types(record_types), accessed_at);
+    }
+
     pub fn pop(&self, store_key: &str) {
         let mut cache_write = self.cache.write().unwrap();
 
@@ -81,6 +137,33 @@ impl StoreCache {
 
         cache_write.remove(store_key);
     }
+
+    pub fn pop_prefixed(&self, key_prefix: &str) {
+        let mut cache_write = self.cache.write().unwrap();
+
+        debug!("store cache pop on keys prefixed with: {}", key_prefix);
+
+        cache_write.retain(|key, _| !key.starts_with(key_prefix));
+    }
+
+    fn get_value(&self, key: &str) -> Result<StoreCacheValue, ()> {
+        let mut cache_write = self.cache.write().unwrap();
+
+        if let Some(ref mut entry) = cache_write.get_mut(key) {
+            // Bump last cache access time
+            entry.accessed_at = SystemTime::now();
+
+            Ok(entry.value.clone())
+        } else {
+            Err(())
+        }
+    }
+
+    fn push_value(&self, key: &str, value: StoreCacheValue, accessed_at: Option<SystemTime>) {
+        let mut cache_write = self.cache.write().unwrap();
+
+        cache_write.insert(key.to_string(), StoreCacheEntry::new(value, accessed_at));
+    }
 }
 
 impl StoreCacheFlush {
@@ -100,7 +183,7 @@ impl StoreCacheFlush {
                     .unwrap()
                     .as_secs();
 
-                if store_elapsed >= APP_CONF.redis.cache_expire_seconds {
+                if store_elapsed >= APP_CONF.load().redis.cache_expire_seconds {
                     expire_register.push(store_key.to_owned());
                 }
             }
@@ -124,7 +207,7 @@ impl StoreCacheFlush {
     pub async fn refresh() {
         debug!("flushing to-be-refreshed store cache records");
 
-        let mut refresh_register: Vec<(String, SystemTime)> = Vec::new();
+        let mut refresh_register: Vec<(String, SystemTime, StoreCacheKind)> = Vec::new();
 
         // Scan for to-be-refreshed store items
         {
@@ -137,21 +220,41 @@ impl StoreCacheFlush {
                     .unwrap()
                     .as_secs();
 
-                if store_elapsed >= APP_CONF.redis.cache_refresh_seconds {
-                    refresh_register.push((store_key.to_owned(), store.accessed_at));
+                if store_elapsed >= APP_CONF.load().redis.cache_refresh_seconds {
+                    refresh_register.push((
+                        store_key.to_owned(),
+                        store.accessed_at,
+                        store.value.kind(),
+                    ));
                 }
             }
         }
 
         // Any store item to refresh?
         if refresh_register.is_empty() == false {
-            for (store_key, store_accessed_at) in &refresh_register {
+            for (store_key, store_accessed_at, store_kind) in &refresh_register {
                 // Notice: restore 'accessed_at' time, otherwise a never-accessed cache entry \
                 //   would never be expired.
-                APP_STORE
-                    .raw_get_remote(store_key, Some(*store_accessed_at))
-                    .await
-                    .ok();
+                match store_kind {
+                    StoreCacheKind::Record => {
+                        APP_STORE
+                            .raw_get_remote(store_key, Some(*store_accessed_at))
+                            .await
+                            .ok();
+                    }
+                    StoreCacheKind::Descendants => {
+                        APP_STORE
+                            .raw_has_descendants_remote(store_key, Some(*store_accessed_at))
+                            .await
+                            .ok();
+                    }
+                    StoreCacheKind::Types => {
+                        APP_STORE
+                            .raw_get_types_remote(store_key, Some(*store_accessed_at))
+                            .await
+                            .ok();
+                    }
+                }
             }
         }
 
@@ -163,13 +266,23 @@ impl StoreCacheFlush {
 }
 
 impl StoreCacheEntry {
-    fn new(record: Option<StoreRecord>, accessed_at: Option<SystemTime>) -> StoreCacheEntry {
+    fn new(value: StoreCacheValue, accessed_at: Option<SystemTime>) -> StoreCacheEntry {
         let time_now = SystemTime::now();
 
         StoreCacheEntry {
-            record: record,
+            value,
             refreshed_at: time_now,
             accessed_at: accessed_at.unwrap_or(time_now),
         }
     }
 }
+
+impl StoreCacheValue {
+    fn kind(&self) -> StoreCacheKind {
+        match self {
+            StoreCacheValue::Record(_) => StoreCacheKind::Record,
+            StoreCacheValue::Descendants(_) => StoreCacheKind::Descendants,
+            StoreCacheValue::Types(_) => StoreCacheKind::Types,
+        }
+    }
+}
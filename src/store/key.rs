@@ -12,6 +12,12 @@ use crate::dns::zone::ZoneName;
 pub struct StoreKey;
 
 pub static KEY_PREFIX: &'static str = "cl";
+pub static KEY_ZONES: &str = "zones";
+pub static KEY_NAMES: &str = "names";
+pub static KEY_TYPES: &str = "types";
+pub static KEY_RECORDS: &str = "records";
+pub static KEY_MIGRATIONS: &str = "migrations";
+pub static KEY_HISTORY: &str = "history";
 
 impl StoreKey {
     pub fn to_key(
@@ -35,6 +41,137 @@ impl StoreKey {
         key
     }
 
+    pub fn to_zones_key() -> String {
+        format!("{}:{}", KEY_PREFIX, KEY_ZONES)
+    }
+
+    pub fn to_migrations_key() -> String {
+        format!("{}:{}", KEY_PREFIX, KEY_MIGRATIONS)
+    }
+
+    pub fn to_zone_pattern(zone_name: &ZoneName) -> String {
+        format!(
+            "{}:{:x}:*",
+            KEY_PREFIX,
+            farmhash::fingerprint32(zone_name.to_str().as_bytes())
+        )
+    }
+
+    pub fn to_types_pattern(zone_name: &ZoneName) -> String {
+        format!(
+            "{}:{}:{:x}:*",
+            KEY_PREFIX,
+            KEY_TYPES,
+            farmhash::fingerprint32(zone_name.to_str().as_bytes())
+        )
+    }
+
+    pub fn to_history_pattern(zone_name: &ZoneName) -> String {
+        format!(
+            "{}:{}:{:x}:*",
+            KEY_PREFIX,
+            KEY_HISTORY,
+            farmhash::fingerprint32(zone_name.to_str().as_bytes())
+        )
+    }
+
+    pub fn to_names_key(zone_name: &ZoneName) -> String {
+        format!(
+            "{}:{}:{:x}",
+            KEY_PREFIX,
+            KEY_NAMES,
+            farmhash::fingerprint32(zone_name.to_str().as_bytes())
+        )
+    }
+
+    pub fn to_history_key(
+        zone_name: &ZoneName,
+        record_name: &RecordName,
+        record_type: &RecordType,
+    ) -> String {
+        format!(
+            "{}:{}:{}",
+            KEY_PREFIX,
+            KEY_HISTORY,
+            Self::hash(zone_name, record_name, record_type)
+        )
+    }
+
+    pub fn to_types_key(zone_name: &ZoneName, record_name: &RecordName) -> String {
+        format!(
+            "{}:{}:{:x}:{:x}",
+            KEY_PREFIX,
+            KEY_TYPES,
+            farmhash::fingerprint32(zone_name.to_str().as_bytes()),
+            farmhash::fingerprint32(record_name.to_str().as_bytes())
+        )
+    }
+
+    pub fn to_records_key(zone_name: &ZoneName) -> String {
+        format!(
+            "{}:{}:{:x}",
+            KEY_PREFIX,
+            KEY_RECORDS,
+            farmhash::fingerprint32(zone_name.to_str().as_bytes())
+        )
+    }
+
+    pub fn to_records_member(record_name: &RecordName, record_type: &RecordType) -> String {
+        // Notice: names are kept as-is, so that records sort by name, then type (names cannot \
+        //   contain the ':' separator).
+        format!("{}:{}", record_name.to_str(), record_type.to_str())
+    }
+
+    pub fn from_records_member(records_member: &str) -> Option<(RecordName, RecordType)> {
+        let (record_name, record_type) = records_member.rsplit_once(':')?;
+
+        Some((
+            RecordName::from_str(record_name)?,
+            RecordType::from_str(record_type)?,
+        ))
+    }
+
+    pub fn to_names_member(record_name: &RecordName) -> String {
+        // Notice: labels are reversed, so that all names below a given name share a common \
+        //   prefix in the lexicographically-ordered index (eg. 'a.b.@' is stored as '@.b.a').
+        record_name
+            .to_str()
+            .rsplit('.')
+            .collect::<Vec<&str>>()
+            .join(".")
+    }
+
+    pub fn to_descendants_key(zone_name: &ZoneName, record_name: &RecordName) -> String {
+        format!(
+            "{}:{}",
+            Self::to_names_key(zone_name),
+            Self::to_names_member(record_name)
+        )
+    }
+
+    pub fn to_ancestors_descendants_keys(
+        zone_name: &ZoneName,
+        record_name: &RecordName,
+    ) -> Vec<String> {
+        let names_key = Self::to_names_key(zone_name);
+        let names_member = Self::to_names_member(record_name);
+
+        let mut keys = Vec::new();
+        let mut ancestor = names_member.as_str();
+
+        while let Some((parent, _)) = ancestor.rsplit_once('.') {
+            keys.push(format!("{}:{}", names_key, parent));
+
+            ancestor = parent;
+        }
+
+        keys
+    }
+
+    pub fn from_descendants_key(descendants_key: &str) -> Option<(&str, &str)> {
+        descendants_key.rsplit_once(':')
+    }
+
     fn hash(zone_name: &ZoneName, record_name: &RecordName, record_type: &RecordType) -> String {
         debug!(
             "hashing record: {} on type: {} for zone: {}",
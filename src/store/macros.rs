@@ -39,8 +39,9 @@ macro_rules! get_cache_store_client {
                         //   since we have waited for the connection timeout time, which \
                         //   means that we should not use the current loop pass instant \
                         //   for correctness.
-                        *pool.delinquent_until.write().unwrap() = Instant::now()
-                            .checked_add(Duration::from_secs(APP_CONF.redis.delinquency_seconds));
+                        *pool.delinquent_until.write().unwrap() = Instant::now().checked_add(
+                            Duration::from_secs(APP_CONF.load().redis.delinquency_seconds),
+                        );
 
                         last_error = $error
                     }
@@ -0,0 +1,98 @@
+// Constellation
+//
+// Pluggable authoritative DNS server
+// Copyright: 2026, Valerian Saliou <valerian@valeriansaliou.name>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use std::time::Duration;
+use tokio::time;
+
+use super::store::StoreError;
+use crate::config::config::ConfigDNSZoneMode;
+use crate::dns::zone::ZoneName;
+use crate::{APP_CONF, APP_STORE};
+
+pub struct StoreMigrateBuilder;
+pub struct StoreMigrate;
+
+static MIGRATION_NAMES: &str = "names";
+static MIGRATION_TYPES: &str = "types";
+static MIGRATION_RECORDS: &str = "records";
+
+const MIGRATE_RETRY_INTERVAL: Duration = Duration::from_secs(10);
+
+impl StoreMigrateBuilder {
+    pub fn new() -> StoreMigrate {
+        StoreMigrate {}
+    }
+}
+
+impl StoreMigrate {
+    #[tokio::main]
+    pub async fn run(&self) {
+        info!("store migrator is now active");
+
+        // Notice: migrations are retried until they succeed, as the store may not be reachable \
+        //   yet upon startup.
+        while Self::perform().await.is_err() {
+            error!("failed running store migrations, will retry");
+
+            time::sleep(MIGRATE_RETRY_INTERVAL).await;
+        }
+
+        info!("ran store migrations");
+    }
+
+    async fn perform() -> Result<(), StoreError> {
+        let migrations = APP_STORE.list_migrations().await?;
+
+        // #1: Build zone names, name types and zone records indexes (from records that were \
+        //   stored before they existed)
+        // Notice: all indexes are built from the same scan, thus they get built at once.
+        let pending: Vec<&str> = [MIGRATION_NAMES, MIGRATION_TYPES, MIGRATION_RECORDS]
+            .iter()
+            .filter(|pending| !migrations.iter().any(|migration| migration == *pending))
+            .copied()
+            .collect();
+
+        if !pending.is_empty() {
+            Self::migrate_index(&pending).await?;
+
+            for migration in pending {
+                APP_STORE.add_migration(migration).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn migrate_index(migrations: &[&str]) -> Result<(), StoreError> {
+        info!("running store migrations: {}", migrations.join(", "));
+
+        let mut zone_names: Vec<String> = APP_CONF
+            .load()
+            .dns
+            .zone
+            .iter()
+            .filter(|(_, zone)| zone.mode != ConfigDNSZoneMode::Secondary)
+            .map(|(zone_name, _)| zone_name.to_owned())
+            .collect();
+
+        zone_names.extend(APP_STORE.list_zones().await?);
+
+        for zone_name in zone_names
+            .iter()
+            .filter_map(|name| ZoneName::from_str(name))
+        {
+            let count = APP_STORE.rebuild_index(&zone_name).await?;
+
+            debug!(
+                "indexed {} store records for zone: {}",
+                count,
+                zone_name.to_str()
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -11,4 +11,5 @@ mod cache;
 mod key;
 
 pub mod flush;
+pub mod migrate;
 pub mod store;
@@ -5,12 +5,14 @@
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
 use bb8_redis::bb8::Pool;
-use bb8_redis::redis::{AsyncCommands, ErrorKind};
+use bb8_redis::redis::aio::ConnectionLike;
+use bb8_redis::redis::{self, AsyncCommands, ErrorKind, Value as RedisValue};
 use bb8_redis::RedisConnectionManager;
 use serde_json::{self, Error as SerdeJSONError};
 use std::collections::HashSet;
+use std::net::IpAddr;
 use std::sync::RwLock;
-use std::time::{Duration, Instant, SystemTime};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use super::cache::STORE_CACHE;
 use super::key::StoreKey;
@@ -29,10 +31,24 @@ static KEY_BLACKHOLE: &'static str = "b";
 static KEY_REGION: &'static str = "r";
 static KEY_RESCUE: &'static str = "f"; // Alias for 'failover'
 static KEY_VALUE: &'static str = "v";
+static KEY_REVISION: &str = "s"; // Alias for 'serial'
 
 const LIMITS_GET_REMOTE_TIMESPAN_TOTAL: Duration = Duration::from_secs(10);
+const ZONE_REMOVE_KEYS_PER_BATCH: usize = 1000;
 const LIMITS_GET_REMOTE_ALLOWANCE_THRESHOLD: Duration = Duration::from_secs(8);
 
+static STORE_GET_FIELDS: [&str; 9] = [
+    KEY_TYPE,
+    KEY_NAME,
+    KEY_TTL,
+    KEY_FLATTEN,
+    KEY_BLACKHOLE,
+    KEY_REGION,
+    KEY_RESCUE,
+    KEY_VALUE,
+    KEY_REVISION,
+];
+
 type StoreGetType = (
     String,
     String,
@@ -42,6 +58,7 @@ type StoreGetType = (
     Option<String>,
     Option<String>,
     String,
+    Option<u64>,
 );
 
 pub struct StoreBuilder;
@@ -66,7 +83,7 @@ pub struct StoreLimitsRate {
     time_spent: Duration,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct StoreRecord {
     pub kind: RecordType,
     pub name: RecordName,
@@ -76,6 +93,37 @@ pub struct StoreRecord {
     pub regions: Option<RecordRegions>,
     pub rescue: Option<RecordValues>,
     pub values: RecordValues,
+    pub revision: Option<u64>,
+}
+
+pub enum StoreOperation {
+    Set(Box<StoreRecord>),
+    Remove(RecordName, RecordType),
+}
+
+pub struct StoreAuthor {
+    pub identity: String,
+    pub source: Option<IpAddr>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct StoreHistoryEntry {
+    pub revision: Option<u64>,
+    pub time: u64,
+    pub identity: String,
+    pub source: Option<IpAddr>,
+    pub previous: Option<StoreRecord>,
+}
+
+#[derive(Default)]
+pub struct StoreCondition {
+    pub if_match: Option<StoreConditionRevisions>,
+    pub if_none_match: Option<StoreConditionRevisions>,
+}
+
+pub enum StoreConditionRevisions {
+    Any,
+    List(Vec<u64>),
 }
 
 pub enum StoreError {
@@ -84,6 +132,7 @@ pub enum StoreError {
     Connector,
     NotFound,
     Disconnected,
+    PreconditionFailed,
 }
 
 #[derive(PartialEq, Clone, Copy)]
@@ -100,14 +149,14 @@ impl StoreBuilder {
         // Bind to master pool
         Self::pool_bind(
             &mut pools,
-            &APP_CONF.redis.master.host,
-            APP_CONF.redis.master.port,
-            &APP_CONF.redis.master.password,
+            &APP_CONF.load().redis.master.host,
+            APP_CONF.load().redis.master.port,
+            &APP_CONF.load().redis.master.password,
         )
         .await;
 
         // Bind to rescue pools (if any)
-        if let Some(ref rescue_items) = APP_CONF.redis.rescue {
+        if let Some(ref rescue_items) = APP_CONF.load().redis.rescue {
             for rescue in rescue_items {
                 Self::pool_bind(&mut pools, &rescue.host, rescue.port, &rescue.password).await;
             }
@@ -148,7 +197,10 @@ impl StoreBuilder {
 
         let tcp_addr_raw = format!(
             "redis://{}{}:{}/{}",
-            &addr_auth, host, port, APP_CONF.redis.database,
+            &addr_auth,
+            host,
+            port,
+            APP_CONF.load().redis.database,
         );
 
         debug!("will connect to redis at: {}", tcp_addr_raw);
@@ -157,15 +209,15 @@ impl StoreBuilder {
             Ok(manager) => {
                 let builder = Pool::builder()
                     .test_on_check_out(true)
-                    .max_size(APP_CONF.redis.pool_size)
+                    .max_size(APP_CONF.load().redis.pool_size)
                     .max_lifetime(Some(Duration::from_secs(
-                        APP_CONF.redis.max_lifetime_seconds,
+                        APP_CONF.load().redis.max_lifetime_seconds,
                     )))
                     .idle_timeout(Some(Duration::from_secs(
-                        APP_CONF.redis.idle_timeout_seconds,
+                        APP_CONF.load().redis.idle_timeout_seconds,
                     )))
                     .connection_timeout(Duration::from_secs(
-                        APP_CONF.redis.connection_timeout_seconds,
+                        APP_CONF.load().redis.connection_timeout_seconds,
                     ));
 
                 match builder.build(manager).await {
@@ -239,142 +291,742 @@ impl Store {
             record_type, zone_name, record_name
         );
 
-        // First, check if limit counters need to be reset and acquire time spent
-        let (time_spent_current_timespan, start_instant) = {
-            let mut limits_rate_write = self.limits.rate.write().unwrap();
-            let now_instant = Instant::now();
+        let start_instant = match self.limits_acquire() {
+            Ok(start_instant) => start_instant,
+            Err(_) => {
+                error!(
+                    "limited remote store get from external on type: {:?}, zone: {:?}, record: {:?}",
+                    record_type, zone_name, record_name
+                );
 
-            // Counters need to be reset?
-            if now_instant.duration_since(limits_rate_write.time_last)
-                >= LIMITS_GET_REMOTE_TIMESPAN_TOTAL
-            {
-                limits_rate_write.time_last = now_instant;
-                limits_rate_write.time_spent = Duration::new(0, 0);
+                // Consider the remote store server to be disconnected, as its network channel \
+                //   is overwhelmed with requests.
+                return Err(StoreError::Disconnected);
+            }
+        };
 
-                debug!(
-                    "started a new time spent chunk in remote store from external ({:?} chunks)",
-                    LIMITS_GET_REMOTE_TIMESPAN_TOTAL
-                );
+        // Read result from remote store
+        let result_remote = self.raw_get_remote(&store_key, None).await;
+
+        self.limits_release(start_instant);
+
+        result_remote
+    }
+
+    pub async fn get_records(
+        &self,
+        zone_name: &ZoneName,
+        records: &[(RecordName, RecordType)],
+    ) -> Result<Vec<Option<StoreRecord>>, StoreError> {
+        // Notice: records are read from the remote store at once, bypassing the local cache \
+        //   (this is used for listings, which would otherwise fill the cache with records that \
+        //   might never be queried over DNS).
+        if records.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        debug!(
+            "get {} records from remote store from internal on zone: {:?}",
+            records.len(),
+            zone_name
+        );
+
+        let mut pipeline = redis::pipe();
+
+        for (record_name, record_type) in records {
+            pipeline.hget(
+                StoreKey::to_key(zone_name, record_name, record_type),
+                &STORE_GET_FIELDS[..],
+            );
+        }
+
+        get_cache_store_client!(&self.pools, StoreError::Disconnected, client {
+            match pipeline.query_async::<_, Vec<RedisValue>>(&mut *client).await {
+                Ok(values) => Ok(
+                    values
+                        .iter()
+                        .map(|value| {
+                            redis::from_redis_value::<StoreGetType>(value)
+                                .ok()
+                                .and_then(Self::decode_record)
+                        })
+                        .collect()
+                ),
+                Err(_) => Err(StoreError::Connector),
             }
+        })
+    }
 
-            (limits_rate_write.time_spent, now_instant)
-        };
+    pub async fn get_types(
+        &self,
+        zone_name: &ZoneName,
+        record_name: &RecordName,
+        origin: StoreAccessOrigin,
+    ) -> Result<Vec<RecordType>, StoreError> {
+        let types_key = StoreKey::to_types_key(zone_name, record_name);
 
-        // Time spent in current timespan is already too great? Reject DNS query.
-        if time_spent_current_timespan >= LIMITS_GET_REMOTE_ALLOWANCE_THRESHOLD {
-            error!(
-                "limited remote store get from external on type: {:?}, zone: {:?}, record: {:?}",
-                record_type, zone_name, record_name
+        // #1. Get from local cache?
+        if let Ok(record_types) = STORE_CACHE.get_types(&types_key) {
+            debug!(
+                "get types from local store from any on zone: {:?}, record: {:?}",
+                zone_name, record_name
             );
 
-            // Consider the remote store server to be disconnected, as its network channel is \
-            //   overwhelmed with requests.
-            return Err(StoreError::Disconnected);
+            return Ok(record_types);
         }
 
-        // Read result from remote store
-        let result_remote = self.raw_get_remote(&store_key, None).await;
+        // #2. Get from store (internal origin? ie. DOS-safe)
+        if origin == StoreAccessOrigin::Internal {
+            return self.raw_get_types_remote(&types_key, None).await;
+        }
 
-        // Update time spent in current timespan
-        {
-            let mut limits_rate_write = self.limits.rate.write().unwrap();
+        // #3. Get from store (external origin, ie. DOS-unsafe, thus we need to apply limits)
+        let start_instant = match self.limits_acquire() {
+            Ok(start_instant) => start_instant,
+            Err(_) => {
+                error!(
+                    "limited remote store types get from external on zone: {:?}, record: {:?}",
+                    zone_name, record_name
+                );
+
+                return Err(StoreError::Disconnected);
+            }
+        };
+
+        let result_remote = self.raw_get_types_remote(&types_key, None).await;
+
+        self.limits_release(start_instant);
+
+        result_remote
+    }
 
-            limits_rate_write.time_spent += start_instant.elapsed();
+    pub async fn has_descendants(
+        &self,
+        zone_name: &ZoneName,
+        record_name: &RecordName,
+        origin: StoreAccessOrigin,
+    ) -> Result<bool, StoreError> {
+        let descendants_key = StoreKey::to_descendants_key(zone_name, record_name);
 
+        // #1. Get from local cache?
+        if let Ok(has_descendants) = STORE_CACHE.get_descendants(&descendants_key) {
             debug!(
-                "updated time spent in remote store from external to: {:?} in current chunk",
-                limits_rate_write.time_spent
+                "get descendants from local store from any on zone: {:?}, record: {:?}",
+                zone_name, record_name
             );
+
+            return Ok(has_descendants);
         }
 
+        // #2. Get from store (internal origin? ie. DOS-safe)
+        if origin == StoreAccessOrigin::Internal {
+            return self
+                .raw_has_descendants_remote(&descendants_key, None)
+                .await;
+        }
+
+        // #3. Get from store (external origin, ie. DOS-unsafe, thus we need to apply limits)
+        // Notice: the same limiting policy as record reads applies here, as this lookup is \
+        //   performed on every non-existing name (ie. random names used in DOS attacks).
+        let start_instant = match self.limits_acquire() {
+            Ok(start_instant) => start_instant,
+            Err(_) => {
+                error!(
+                    "limited remote store descendants get from external on zone: {:?}, record: {:?}",
+                    zone_name, record_name
+                );
+
+                return Err(StoreError::Disconnected);
+            }
+        };
+
+        let result_remote = self
+            .raw_has_descendants_remote(&descendants_key, None)
+            .await;
+
+        self.limits_release(start_instant);
+
         result_remote
     }
 
-    pub async fn set(&self, zone_name: &ZoneName, record: StoreRecord) -> Result<(), StoreError> {
+    pub async fn apply(
+        &self,
+        zone_name: &ZoneName,
+        operations: Vec<StoreOperation>,
+        author: &StoreAuthor,
+    ) -> Result<(), StoreError> {
+        self.apply_if(zone_name, operations, &StoreCondition::default(), author)
+            .await
+    }
+
+    pub async fn apply_if(
+        &self,
+        zone_name: &ZoneName,
+        operations: Vec<StoreOperation>,
+        condition: &StoreCondition,
+        author: &StoreAuthor,
+    ) -> Result<(), StoreError> {
+        self.apply_checked(zone_name, operations, condition, &[], author)
+            .await
+    }
+
+    pub async fn apply_expected(
+        &self,
+        zone_name: &ZoneName,
+        operations: Vec<StoreOperation>,
+        expected: &[(RecordName, RecordType, Option<u64>)],
+        author: &StoreAuthor,
+    ) -> Result<(), StoreError> {
+        self.apply_checked(
+            zone_name,
+            operations,
+            &StoreCondition::default(),
+            expected,
+            author,
+        )
+        .await
+    }
+
+    async fn apply_checked(
+        &self,
+        zone_name: &ZoneName,
+        operations: Vec<StoreOperation>,
+        condition: &StoreCondition,
+        expected: &[(RecordName, RecordType, Option<u64>)],
+        author: &StoreAuthor,
+    ) -> Result<(), StoreError> {
+        // Encode all records first, so that nothing gets written if any record cannot be encoded
+        let mut encoded_fields = Vec::new();
+
+        for operation in operations.iter() {
+            encoded_fields.push(match operation {
+                StoreOperation::Set(record) => Some(Self::encode_record(record)?),
+                StoreOperation::Remove(_, _) => None,
+            });
+        }
+
+        // Names that had records removed, and that may thus be left without any record
+        let mut removed_names: Vec<&RecordName> = Vec::new();
+
+        for operation in operations.iter() {
+            if let StoreOperation::Remove(record_name, _) = operation {
+                if !removed_names.contains(&record_name) {
+                    removed_names.push(record_name);
+                }
+            }
+        }
+
+        let removed_types_keys: Vec<String> = removed_names
+            .iter()
+            .map(|record_name| StoreKey::to_types_key(zone_name, record_name))
+            .collect();
+
+        let store_keys: Vec<(String, String)> = operations
+            .iter()
+            .map(|operation| {
+                let (record_name, record_type) = operation.key();
+
+                (
+                    StoreKey::to_key(zone_name, record_name, record_type),
+                    StoreKey::to_history_key(zone_name, record_name, record_type),
+                )
+            })
+            .collect();
+
+        let expected_keys: Vec<(String, String)> = expected
+            .iter()
+            .map(|(record_name, record_type, _)| {
+                (
+                    StoreKey::to_key(zone_name, record_name, record_type),
+                    StoreKey::to_history_key(zone_name, record_name, record_type),
+                )
+            })
+            .collect();
+
         get_cache_store_client!(&self.pools, StoreError::Disconnected, client {
-            let flatten_encoder: Result<String, SerdeJSONError> = match record.flatten {
-                Some(true) => {
-                    Ok("1".to_owned())
-                },
-                _ => Ok("".to_owned())
-            };
-            let blackhole_encoder = match record.blackhole {
-                Some(ref blackhole) => {
-                    if blackhole.has_items() == true {
-                        serde_json::to_string(blackhole)
-                    } else {
-                        Ok("".to_owned())
+            // Write to remote (along with record types in the name types index, names in the \
+            //   zone names index, records in the zone records index, and replaced records in \
+            //   their history)
+            // Notice: all operations are written in a transaction, so that resolvers never \
+            //   observe half-applied changes, and that the indexes never miss a record. Records \
+            //   and the name types index of removed names are watched, so that the transaction \
+            //   is retried if any record got changed concurrently (ie. after it got read to \
+            //   check conditions and fill its history). Expected records are watched as well, \
+            //   so that they cannot change between their check and the write.
+            loop {
+                redis::cmd("WATCH")
+                    .arg(&removed_types_keys)
+                    .arg(
+                        store_keys
+                            .iter()
+                            .chain(expected_keys.iter())
+                            .map(|(store_key, _)| store_key)
+                            .collect::<Vec<&String>>()
+                    )
+                    .query_async::<_, ()>(&mut *client)
+                    .await
+                    .or(Err(StoreError::Connector))?;
+
+                let current_records = match Self::read_records(&mut *client, &store_keys).await {
+                    Ok(current_records) => current_records,
+                    Err(err) => {
+                        redis::cmd("UNWATCH").query_async::<_, ()>(&mut *client).await.ok();
+
+                        return Err(err);
                     }
-                },
-                None => Ok("".to_owned())
-            };
-            let region_encoder = match record.regions {
-                Some(ref regions) => serde_json::to_string(regions),
-                None => Ok("".to_owned())
-            };
-            let rescue_encoder = match record.rescue {
-                Some(ref rescue) => {
-                    if rescue.is_empty() == false {
-                        serde_json::to_string(rescue)
-                    } else {
-                        Ok("".to_owned())
+                };
+
+                if condition.is_set()
+                    && current_records
+                        .iter()
+                        .any(|(current_record, _)| {
+                            !condition.check(
+                                current_record.as_ref().and_then(|record| record.revision)
+                            )
+                        })
+                {
+                    redis::cmd("UNWATCH").query_async::<_, ()>(&mut *client).await.ok();
+
+                    return Err(StoreError::PreconditionFailed);
+                }
+
+                // Check that expected records are still at the revision they were read at (a \
+                //   'None' revision means that the record was expected not to exist)
+                if !expected_keys.is_empty() {
+                    let expected_records = match Self::read_records(
+                        &mut *client, &expected_keys
+                    ).await {
+                        Ok(expected_records) => expected_records,
+                        Err(err) => {
+                            redis::cmd("UNWATCH").query_async::<_, ()>(&mut *client).await.ok();
+
+                            return Err(err);
+                        }
+                    };
+
+                    if expected_records
+                        .iter()
+                        .zip(expected.iter())
+                        .any(|((expected_record, _), (_, _, revision))| {
+                            expected_record.as_ref().and_then(|record| record.revision)
+                                != *revision
+                        })
+                    {
+                        redis::cmd("UNWATCH").query_async::<_, ()>(&mut *client).await.ok();
+
+                        return Err(StoreError::PreconditionFailed);
                     }
-                },
-                None => Ok("".to_owned())
-            };
+                }
 
-            match (
-                serde_json::to_string(&record.values),
-                flatten_encoder,
-                blackhole_encoder,
-                region_encoder,
-                rescue_encoder
-            ) {
-                (Ok(values), Ok(flatten), Ok(blackhole), Ok(regions), Ok(rescue)) => {
-                    let store_key = StoreKey::to_key(zone_name, &record.name, &record.kind);
-
-                    // Clean from local cache
-                    STORE_CACHE.pop(&store_key);
-
-                    // Store in remote
-                    client.hset_multiple(
-                        store_key, &[
-                            (KEY_TYPE, record.kind.to_str()),
-                            (KEY_NAME, record.name.to_str()),
-                            (KEY_TTL, &record.ttl.unwrap_or(0).to_string()),
-                            (KEY_FLATTEN, &flatten),
-                            (KEY_BLACKHOLE, &blackhole),
-                            (KEY_REGION, &regions),
-                            (KEY_RESCUE, &rescue),
-                            (KEY_VALUE, &values),
-                        ]
-                    ).await.or(Err(StoreError::Connector))
-                },
-                (Err(_), _, _, _, _) |
-                (_, Err(_), _, _, _) |
-                (_, _, Err(_), _, _) |
-                (_, _, _, Err(_), _) |
-                (_, _, _, _, Err(_)) => {
-                    Err(StoreError::Encoding)
+                let mut emptied_names = Vec::new();
+
+                for (record_name, types_key) in removed_names.iter().zip(removed_types_keys.iter()) {
+                    let record_types = match client.smembers::<_, Vec<String>>(types_key).await {
+                        Ok(record_types) => record_types,
+                        Err(_) => {
+                            redis::cmd("UNWATCH").query_async::<_, ()>(&mut *client).await.ok();
+
+                            return Err(StoreError::Connector);
+                        }
+                    };
+
+                    if !Self::has_records_after(&operations, record_name, &record_types) {
+                        emptied_names.push(*record_name);
+                    }
+                }
+
+                let mut pipeline = redis::pipe();
+
+                pipeline.atomic();
+
+                for (((operation, fields), (store_key, history_key)), (current_record, last_revision)) in
+                    operations
+                        .iter()
+                        .zip(encoded_fields.iter())
+                        .zip(store_keys.iter())
+                        .zip(current_records.into_iter())
+                {
+                    match (operation, fields) {
+                        (StoreOperation::Set(record), Some(fields)) => {
+                            let revision = last_revision + 1;
+
+                            pipeline
+                                .hset_multiple(store_key, fields)
+                                .ignore()
+                                .hset(store_key, KEY_REVISION, revision)
+                                .ignore()
+                                .sadd(
+                                    StoreKey::to_types_key(zone_name, &record.name),
+                                    record.kind.to_str(),
+                                )
+                                .ignore()
+                                .zadd(
+                                    StoreKey::to_names_key(zone_name),
+                                    StoreKey::to_names_member(&record.name),
+                                    0,
+                                )
+                                .ignore()
+                                .zadd(
+                                    StoreKey::to_records_key(zone_name),
+                                    StoreKey::to_records_member(&record.name, &record.kind),
+                                    0,
+                                )
+                                .ignore();
+
+                            if let Err(err) = Self::pipe_history(
+                                &mut pipeline,
+                                history_key,
+                                author,
+                                Some(revision),
+                                current_record,
+                            ) {
+                                redis::cmd("UNWATCH").query_async::<_, ()>(&mut *client).await.ok();
+
+                                return Err(err);
+                            }
+                        }
+                        (StoreOperation::Remove(record_name, record_type), _) => {
+                            pipeline
+                                .del(store_key)
+                                .ignore()
+                                .srem(
+                                    StoreKey::to_types_key(zone_name, record_name),
+                                    record_type.to_str(),
+                                )
+                                .ignore()
+                                .zrem(
+                                    StoreKey::to_records_key(zone_name),
+                                    StoreKey::to_records_member(record_name, record_type),
+                                )
+                                .ignore();
+
+                            // Notice: removing a missing record does not change anything, thus \
+                            //   it is not kept in history.
+                            if current_record.is_some() {
+                                if let Err(err) = Self::pipe_history(
+                                    &mut pipeline,
+                                    history_key,
+                                    author,
+                                    None,
+                                    current_record,
+                                ) {
+                                    redis::cmd("UNWATCH")
+                                        .query_async::<_, ()>(&mut *client)
+                                        .await
+                                        .ok();
+
+                                    return Err(err);
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+
+                for record_name in emptied_names {
+                    pipeline.zrem(
+                        StoreKey::to_names_key(zone_name),
+                        StoreKey::to_names_member(record_name)
+                    ).ignore();
+                }
+
+                match pipeline.query_async::<_, Option<()>>(&mut *client).await {
+                    Ok(Some(_)) => break,
+                    Ok(None) => {
+                        debug!("retrying store operations on zone: {}", zone_name.to_str());
+                    },
+                    Err(_) => {
+                        redis::cmd("UNWATCH").query_async::<_, ()>(&mut *client).await.ok();
+
+                        return Err(StoreError::Connector);
+                    }
                 }
             }
+
+            // Clean from local cache
+            for operation in operations.iter() {
+                let (record_name, record_type) = operation.key();
+
+                Self::pop_cache(
+                    zone_name,
+                    record_name,
+                    &StoreKey::to_key(zone_name, record_name, record_type)
+                );
+            }
+
+            Ok(())
         })
     }
 
-    pub async fn remove(
+    pub async fn modify<F>(
         &self,
         zone_name: &ZoneName,
         record_name: &RecordName,
         record_type: &RecordType,
-    ) -> Result<(), StoreError> {
+        condition: &StoreCondition,
+        author: &StoreAuthor,
+        modifier: F,
+    ) -> Result<Option<StoreRecord>, StoreError>
+    where
+        F: Fn(StoreRecord) -> Option<StoreRecord>,
+    {
+        let store_keys = vec![(
+            StoreKey::to_key(zone_name, record_name, record_type),
+            StoreKey::to_history_key(zone_name, record_name, record_type),
+        )];
+
+        let (store_key, history_key) = &store_keys[0];
+
         get_cache_store_client!(&self.pools, StoreError::Disconnected, client {
-            let store_key = StoreKey::to_key(zone_name, record_name, record_type);
+            // Read, modify then write back the record to remote (and the replaced record to its \
+            //   history)
+            // Notice: the record is watched, so that the modification is retried on the latest \
+            //   record if it got written or removed concurrently. Its name and type are left \
+            //   unchanged, thus indexes do not need to be updated.
+            let record = loop {
+                redis::cmd("WATCH")
+                    .arg(store_key)
+                    .query_async::<_, ()>(&mut *client)
+                    .await
+                    .or(Err(StoreError::Connector))?;
+
+                let current_record = match Self::read_records(&mut *client, &store_keys).await {
+                    Ok(mut current_records) => match current_records.pop() {
+                        Some((Some(record), _)) if !condition.check(record.revision) => {
+                            Err(StoreError::PreconditionFailed)
+                        },
+                        Some((Some(record), _)) => Ok(record),
+                        _ if !condition.check(None) => Err(StoreError::PreconditionFailed),
+                        _ => Err(StoreError::NotFound),
+                    },
+                    Err(err) => Err(err),
+                };
+
+                let result = current_record.and_then(|current_record| {
+                    let revision = current_record.revision.unwrap_or(0) + 1;
+
+                    match modifier(current_record.clone()) {
+                        Some(record) => {
+                            let fields = Self::encode_record(&record)?;
+
+                            let mut pipeline = redis::pipe();
+
+                            pipeline
+                                .atomic()
+                                .hset_multiple(store_key, &fields)
+                                .ignore()
+                                .hset(store_key, KEY_REVISION, revision)
+                                .ignore();
+
+                            Self::pipe_history(
+                                &mut pipeline,
+                                history_key,
+                                author,
+                                Some(revision),
+                                Some(current_record),
+                            )?;
+
+                            Ok(Some((
+                                StoreRecord {
+                                    revision: Some(revision),
+                                    ..record
+                                },
+                                pipeline,
+                            )))
+                        },
+                        None => Ok(None),
+                    }
+                });
+
+                let (record, pipeline) = match result {
+                    Ok(Some(result)) => result,
+                    Ok(None) => {
+                        redis::cmd("UNWATCH").query_async::<_, ()>(&mut *client).await.ok();
+
+                        return Ok(None);
+                    },
+                    Err(err) => {
+                        redis::cmd("UNWATCH").query_async::<_, ()>(&mut *client).await.ok();
+
+                        return Err(err);
+                    }
+                };
+
+                match pipeline.query_async::<_, Option<()>>(&mut *client).await {
+                    Ok(Some(_)) => break record,
+                    Ok(None) => {
+                        debug!("retrying store record modification at key: {}", store_key);
+                    },
+                    Err(_) => {
+                        redis::cmd("UNWATCH").query_async::<_, ()>(&mut *client).await.ok();
+
+                        return Err(StoreError::Connector);
+                    }
+                }
+            };
 
             // Clean from local cache
-            STORE_CACHE.pop(&store_key);
+            Self::pop_cache(zone_name, record_name, store_key);
+
+            Ok(Some(record))
+        })
+    }
+
+    pub async fn list_history(
+        &self,
+        zone_name: &ZoneName,
+        record_name: &RecordName,
+        record_type: &RecordType,
+    ) -> Result<Vec<StoreHistoryEntry>, StoreError> {
+        get_cache_store_client!(&self.pools, StoreError::Disconnected, client {
+            match client.lrange::<_, Vec<String>>(
+                StoreKey::to_history_key(zone_name, record_name, record_type),
+                0,
+                -1
+            ).await {
+                Ok(entries) => Ok(
+                    entries
+                        .iter()
+                        .filter_map(|entry| serde_json::from_str(entry).ok())
+                        .collect()
+                ),
+                Err(_) => Err(StoreError::Connector),
+            }
+        })
+    }
+
+    pub async fn list_records(
+        &self,
+        zone_name: &ZoneName,
+        cursor: Option<&str>,
+        prefix: Option<&str>,
+        count: usize,
+    ) -> Result<Vec<(String, Option<(RecordName, RecordType)>)>, StoreError> {
+        // Notice: records are listed in lexicographical order, starting right after the cursor \
+        //   (ie. the last listed index member), and bounded to index members with the prefix. \
+        //   Index members that cannot be parsed are still returned (without a record), so that \
+        //   callers can walk past them.
+        let mut range_min = match prefix {
+            Some(prefix) => format!("[{}", prefix).into_bytes(),
+            None => b"-".to_vec(),
+        };
+
+        if let Some(cursor) = cursor {
+            if prefix.map(|prefix| cursor >= prefix).unwrap_or(true) {
+                range_min = format!("({}", cursor).into_bytes();
+            }
+        }
+
+        let range_max = match prefix {
+            Some(prefix) => [b"[", prefix.as_bytes(), &[0xff]].concat(),
+            None => b"+".to_vec(),
+        };
+
+        get_cache_store_client!(&self.pools, StoreError::Disconnected, client {
+            match client.zrangebylex_limit::<_, _, _, Vec<String>>(
+                StoreKey::to_records_key(zone_name),
+                &range_min,
+                &range_max,
+                0,
+                count as isize,
+            ).await {
+                Ok(records_members) => Ok(
+                    records_members
+                        .into_iter()
+                        .map(|records_member| {
+                            let record = StoreKey::from_records_member(&records_member);
+
+                            (records_member, record)
+                        })
+                        .collect()
+                ),
+                Err(_) => Err(StoreError::Connector),
+            }
+        })
+    }
+
+    pub async fn list_zones(&self) -> Result<Vec<String>, StoreError> {
+        get_cache_store_client!(&self.pools, StoreError::Disconnected, client {
+            client
+                .smembers(StoreKey::to_zones_key())
+                .await
+                .or(Err(StoreError::Connector))
+        })
+    }
 
-            // Delete from remote
-            client.del(store_key).await.or(Err(StoreError::Connector))
+    pub async fn add_zone(&self, zone_name: &ZoneName) -> Result<(), StoreError> {
+        get_cache_store_client!(&self.pools, StoreError::Disconnected, client {
+            client
+                .sadd(StoreKey::to_zones_key(), zone_name.to_str())
+                .await
+                .or(Err(StoreError::Connector))
+        })
+    }
+
+    pub async fn remove_zone(&self, zone_name: &ZoneName) -> Result<bool, StoreError> {
+        let (names_key, records_key) = (
+            StoreKey::to_names_key(zone_name),
+            StoreKey::to_records_key(zone_name),
+        );
+        let zone_patterns = [
+            StoreKey::to_zone_pattern(zone_name),
+            StoreKey::to_types_pattern(zone_name),
+            StoreKey::to_history_pattern(zone_name),
+        ];
+
+        get_cache_store_client!(&self.pools, StoreError::Disconnected, client {
+            let is_zone = client
+                .sismember::<_, _, bool>(StoreKey::to_zones_key(), zone_name.to_str())
+                .await
+                .or(Err(StoreError::Connector))?;
+
+            if !is_zone {
+                return Ok(false);
+            }
+
+            // Remove all zone keys from remote (ie. records, indexes and history), so that a \
+            //   zone created again with the same name starts empty
+            // Notice: the zone is only removed from zones once all its keys are removed, so that \
+            //   removing it can be retried if this fails half-way.
+            let mut zone_keys = vec![names_key.to_owned(), records_key];
+
+            for zone_pattern in &zone_patterns {
+                let mut iter = client
+                    .scan_match::<_, String>(zone_pattern)
+                    .await
+                    .or(Err(StoreError::Connector))?;
+
+                while let Some(zone_key) = iter.next_item().await {
+                    zone_keys.push(zone_key);
+                }
+            }
+
+            for zone_keys_batch in zone_keys.chunks(ZONE_REMOVE_KEYS_PER_BATCH) {
+                client
+                    .del::<_, ()>(zone_keys_batch)
+                    .await
+                    .or(Err(StoreError::Connector))?;
+            }
+
+            client
+                .srem::<_, _, ()>(StoreKey::to_zones_key(), zone_name.to_str())
+                .await
+                .or(Err(StoreError::Connector))?;
+
+            debug!(
+                "removed zone: {} along with {} keys",
+                zone_name.to_str(),
+                zone_keys.len()
+            );
+
+            // Clean from local cache (records, types and descendants)
+            for zone_pattern in &zone_patterns {
+                STORE_CACHE.pop_prefixed(zone_pattern.trim_end_matches('*'));
+            }
+
+            STORE_CACHE.pop_prefixed(&format!("{}:", names_key));
+
+            Ok(true)
         })
     }
 
@@ -384,100 +1036,9 @@ impl Store {
         cache_accessed_at: Option<SystemTime>,
     ) -> Result<StoreRecord, StoreError> {
         get_cache_store_client!(&self.pools, StoreError::Disconnected, client {
-            match client.hget::<_, _, StoreGetType>(
-                store_key,
-
-                (
-                    KEY_TYPE,
-                    KEY_NAME,
-                    KEY_TTL,
-                    KEY_FLATTEN,
-                    KEY_BLACKHOLE,
-                    KEY_REGION,
-                    KEY_RESCUE,
-                    KEY_VALUE
-                ),
-            ).await {
+            match client.hget::<_, _, StoreGetType>(store_key, &STORE_GET_FIELDS[..]).await {
                 Ok(values) => {
-                    if let (Some(kind_value), Some(name_value), Ok(value_value)) = (
-                        RecordType::from_str(&values.0),
-                        RecordName::from_str(&values.1),
-                        serde_json::from_str(&values.7)
-                    ) {
-                        let ttl = if values.2 > 0 {
-                            Some(values.2)
-                        } else {
-                            None
-                        };
-
-                        let flatten = values.3.and_then(|flatten_raw| {
-                            if flatten_raw == "1" {
-                                Some(true)
-                            } else {
-                                None
-                            }
-                        });
-                        let blackhole = values.4.and_then(|blackhole_raw| {
-                            serde_json::from_str::<RecordBlackhole>(&blackhole_raw).ok()
-                        });
-                        let regions = values.5.and_then(|region_raw| {
-                            serde_json::from_str::<RecordRegions>(&region_raw).ok()
-                        });
-                        let rescue = values.6.and_then(|rescue_raw| {
-                            serde_json::from_str::<RecordValues>(&rescue_raw).ok()
-                        });
-
-                        debug!(
-                            "read store record with kind: {:?}, name: {:?} and values: {:?}",
-                            kind_value,
-                            name_value,
-                            value_value
-                        );
-
-                        if flatten.is_some() == true {
-                            debug!(
-                                "store record with kind: {:?}, name: {:?} has flatten: {:?}",
-                                kind_value,
-                                name_value,
-                                flatten
-                            );
-                        }
-                        if blackhole.is_some() == true {
-                            debug!(
-                                "store record with kind: {:?}, name: {:?} has blackhole: {:?}",
-                                kind_value,
-                                name_value,
-                                blackhole
-                            );
-                        }
-                        if regions.is_some() == true {
-                            debug!(
-                                "store record with kind: {:?}, name: {:?} has regions: {:?}",
-                                kind_value,
-                                name_value,
-                                regions
-                            );
-                        }
-                        if rescue.is_some() == true {
-                             debug!(
-                                "store record with kind: {:?}, name: {:?} has rescue: {:?}",
-                                kind_value,
-                                name_value,
-                                rescue
-                            );
-                        }
-
-                        let record = StoreRecord {
-                            kind: kind_value,
-                            name: name_value,
-                            ttl: ttl,
-                            flatten: flatten,
-                            blackhole: blackhole,
-                            regions: regions,
-                            rescue: rescue,
-                            values: value_value,
-                        };
-
+                    if let Some(record) = Self::decode_record(values) {
                         // Store in local cache
                         STORE_CACHE.push(store_key, Some(record.clone()), cache_accessed_at);
 
@@ -503,6 +1064,435 @@ impl Store {
             }
         })
     }
+
+    pub async fn raw_get_types_remote(
+        &self,
+        types_key: &str,
+        cache_accessed_at: Option<SystemTime>,
+    ) -> Result<Vec<RecordType>, StoreError> {
+        get_cache_store_client!(&self.pools, StoreError::Disconnected, client {
+            match client.smembers::<_, Vec<String>>(types_key).await {
+                Ok(values) => {
+                    let record_types: Vec<RecordType> = values
+                        .iter()
+                        .filter_map(|value| RecordType::from_str(value))
+                        .collect();
+
+                    debug!("read store types at key: {}, got: {:?}", types_key, record_types);
+
+                    // Store in local cache
+                    STORE_CACHE.push_types(types_key, record_types.clone(), cache_accessed_at);
+
+                    Ok(record_types)
+                },
+                Err(err) => {
+                    debug!("could not read store types at key: {}, because: {}", types_key, err);
+
+                    Err(StoreError::Connector)
+                },
+            }
+        })
+    }
+
+    pub async fn raw_has_descendants_remote(
+        &self,
+        descendants_key: &str,
+        cache_accessed_at: Option<SystemTime>,
+    ) -> Result<bool, StoreError> {
+        let (names_key, names_member) =
+            StoreKey::from_descendants_key(descendants_key).ok_or(StoreError::Corrupted)?;
+
+        get_cache_store_client!(&self.pools, StoreError::Disconnected, client {
+            // Notice: names are stored with their labels reversed, thus any name below this \
+            //   name sorts between '<name>.' (inclusive) and '<name>/' (exclusive).
+            match client.zrangebylex_limit::<_, _, _, Vec<String>>(
+                names_key,
+                format!("[{}.", names_member),
+                format!("({}/", names_member),
+                0,
+                1,
+            ).await {
+                Ok(descendants) => {
+                    let has_descendants = !descendants.is_empty();
+
+                    debug!(
+                        "read store descendants at key: {}, got: {}",
+                        descendants_key, has_descendants
+                    );
+
+                    // Store in local cache
+                    STORE_CACHE.push_descendants(
+                        descendants_key,
+                        has_descendants,
+                        cache_accessed_at,
+                    );
+
+                    Ok(has_descendants)
+                },
+                Err(err) => {
+                    debug!(
+                        "could not read store descendants at key: {}, because: {}",
+                        descendants_key, err
+                    );
+
+                    Err(StoreError::Connector)
+                },
+            }
+        })
+    }
+
+    pub async fn list_migrations(&self) -> Result<Vec<String>, StoreError> {
+        get_cache_store_client!(&self.pools, StoreError::Disconnected, client {
+            client
+                .smembers(StoreKey::to_migrations_key())
+                .await
+                .or(Err(StoreError::Connector))
+        })
+    }
+
+    pub async fn add_migration(&self, migration: &str) -> Result<(), StoreError> {
+        get_cache_store_client!(&self.pools, StoreError::Disconnected, client {
+            client
+                .sadd(StoreKey::to_migrations_key(), migration)
+                .await
+                .or(Err(StoreError::Connector))
+        })
+    }
+
+    pub async fn rebuild_index(&self, zone_name: &ZoneName) -> Result<usize, StoreError> {
+        get_cache_store_client!(&self.pools, StoreError::Disconnected, client {
+            let store_keys: Vec<String> = {
+                let mut iter = client
+                    .scan_match::<_, String>(StoreKey::to_zone_pattern(zone_name))
+                    .await
+                    .or(Err(StoreError::Connector))?;
+
+                let mut store_keys = Vec::new();
+
+                while let Some(store_key) = iter.next_item().await {
+                    store_keys.push(store_key);
+                }
+
+                store_keys
+            };
+
+            let (names_key, records_key) = (
+                StoreKey::to_names_key(zone_name),
+                StoreKey::to_records_key(zone_name),
+            );
+            let mut count = 0;
+
+            for store_key in &store_keys {
+                let (kind, name): (Option<String>, Option<String>) = client
+                    .hget(store_key, (KEY_TYPE, KEY_NAME))
+                    .await
+                    .or(Err(StoreError::Connector))?;
+
+                if let (Some(record_type), Some(record_name)) = (
+                    kind.as_deref().and_then(RecordType::from_str),
+                    name.as_deref().and_then(RecordName::from_str),
+                ) {
+                    redis::pipe()
+                        .atomic()
+                        .sadd(
+                            StoreKey::to_types_key(zone_name, &record_name),
+                            record_type.to_str(),
+                        )
+                        .ignore()
+                        .zadd(&names_key, StoreKey::to_names_member(&record_name), 0)
+                        .ignore()
+                        .zadd(
+                            &records_key,
+                            StoreKey::to_records_member(&record_name, &record_type),
+                            0,
+                        )
+                        .ignore()
+                        .query_async::<_, ()>(&mut *client)
+                        .await
+                        .or(Err(StoreError::Connector))?;
+
+                    count += 1;
+                }
+            }
+
+            Ok(count)
+        })
+    }
+
+    fn decode_record(values: StoreGetType) -> Option<StoreRecord> {
+        if let (Some(kind_value), Some(name_value), Ok(value_value)) = (
+            RecordType::from_str(&values.0),
+            RecordName::from_str(&values.1),
+            serde_json::from_str(&values.7),
+        ) {
+            let ttl = if values.2 > 0 { Some(values.2) } else { None };
+
+            let flatten = values.3.and_then(
+                |flatten_raw| {
+                    if flatten_raw == "1" {
+                        Some(true)
+                    } else {
+                        None
+                    }
+                },
+            );
+            let blackhole = values.4.and_then(|blackhole_raw| {
+                serde_json::from_str::<RecordBlackhole>(&blackhole_raw).ok()
+            });
+            let regions = values
+                .5
+                .and_then(|region_raw| serde_json::from_str::<RecordRegions>(&region_raw).ok());
+            let rescue = values
+                .6
+                .and_then(|rescue_raw| serde_json::from_str::<RecordValues>(&rescue_raw).ok());
+
+            debug!(
+                "read store record with kind: {:?}, name: {:?} and values: {:?}",
+                kind_value, name_value, value_value
+            );
+
+            if flatten.is_some() {
+                debug!(
+                    "store record with kind: {:?}, name: {:?} has flatten: {:?}",
+                    kind_value, name_value, flatten
+                );
+            }
+            if blackhole.is_some() {
+                debug!(
+                    "store record with kind: {:?}, name: {:?} has blackhole: {:?}",
+                    kind_value, name_value, blackhole
+                );
+            }
+            if regions.is_some() {
+                debug!(
+                    "store record with kind: {:?}, name: {:?} has regions: {:?}",
+                    kind_value, name_value, regions
+                );
+            }
+            if rescue.is_some() {
+                debug!(
+                    "store record with kind: {:?}, name: {:?} has rescue: {:?}",
+                    kind_value, name_value, rescue
+                );
+            }
+
+            Some(StoreRecord {
+                kind: kind_value,
+                name: name_value,
+                ttl,
+                flatten,
+                blackhole,
+                regions,
+                rescue,
+                values: value_value,
+                revision: Some(values.8.unwrap_or(0)),
+            })
+        } else {
+            None
+        }
+    }
+
+    fn encode_record(record: &StoreRecord) -> Result<Vec<(&'static str, String)>, StoreError> {
+        let flatten_encoder: Result<String, SerdeJSONError> = match record.flatten {
+            Some(true) => Ok("1".to_owned()),
+            _ => Ok("".to_owned()),
+        };
+        let blackhole_encoder = match record.blackhole {
+            Some(ref blackhole) => {
+                if blackhole.has_items() {
+                    serde_json::to_string(blackhole)
+                } else {
+                    Ok("".to_owned())
+                }
+            }
+            None => Ok("".to_owned()),
+        };
+        let region_encoder = match record.regions {
+            Some(ref regions) => serde_json::to_string(regions),
+            None => Ok("".to_owned()),
+        };
+        let rescue_encoder = match record.rescue {
+            Some(ref rescue) => {
+                if !rescue.is_empty() {
+                    serde_json::to_string(rescue)
+                } else {
+                    Ok("".to_owned())
+                }
+            }
+            None => Ok("".to_owned()),
+        };
+
+        match (
+            serde_json::to_string(&record.values),
+            flatten_encoder,
+            blackhole_encoder,
+            region_encoder,
+            rescue_encoder,
+        ) {
+            (Ok(values), Ok(flatten), Ok(blackhole), Ok(regions), Ok(rescue)) => Ok(vec![
+                (KEY_TYPE, record.kind.to_str().to_owned()),
+                (KEY_NAME, record.name.to_str().to_owned()),
+                (KEY_TTL, record.ttl.unwrap_or(0).to_string()),
+                (KEY_FLATTEN, flatten),
+                (KEY_BLACKHOLE, blackhole),
+                (KEY_REGION, regions),
+                (KEY_RESCUE, rescue),
+                (KEY_VALUE, values),
+            ]),
+            _ => Err(StoreError::Encoding),
+        }
+    }
+
+    async fn read_records<C: ConnectionLike>(
+        client: &mut C,
+        store_keys: &[(String, String)],
+    ) -> Result<Vec<(Option<StoreRecord>, u64)>, StoreError> {
+        let mut pipeline = redis::pipe();
+
+        for (store_key, history_key) in store_keys {
+            pipeline
+                .hget(store_key, &STORE_GET_FIELDS[..])
+                .lindex(history_key, 0);
+        }
+
+        let values = pipeline
+            .query_async::<_, Vec<RedisValue>>(client)
+            .await
+            .or(Err(StoreError::Connector))?;
+
+        // Notice: the last revision is the highest of the current record revision and the last \
+        //   revision in history, so that revisions are never re-used (eg. when a removed record \
+        //   is set again).
+        Ok(values
+            .chunks(2)
+            .map(|values| {
+                let record = redis::from_redis_value::<StoreGetType>(&values[0])
+                    .ok()
+                    .and_then(Self::decode_record);
+
+                let history_revision = redis::from_redis_value::<String>(&values[1])
+                    .ok()
+                    .and_then(|entry| serde_json::from_str::<StoreHistoryEntry>(&entry).ok())
+                    .and_then(|entry| {
+                        entry
+                            .revision
+                            .or(entry.previous.and_then(|previous| previous.revision))
+                    });
+
+                let last_revision = record
+                    .as_ref()
+                    .and_then(|record| record.revision)
+                    .max(history_revision)
+                    .unwrap_or(0);
+
+                (record, last_revision)
+            })
+            .collect())
+    }
+
+    fn pipe_history(
+        pipeline: &mut redis::Pipeline,
+        history_key: &str,
+        author: &StoreAuthor,
+        revision: Option<u64>,
+        previous: Option<StoreRecord>,
+    ) -> Result<(), StoreError> {
+        let history_size = APP_CONF.load().redis.history_size;
+
+        if history_size > 0 {
+            let entry = serde_json::to_string(&StoreHistoryEntry {
+                revision,
+                time: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|duration| duration.as_secs())
+                    .unwrap_or(0),
+                identity: author.identity.to_owned(),
+                source: author.source,
+                previous,
+            })
+            .or(Err(StoreError::Encoding))?;
+
+            pipeline
+                .lpush(history_key, entry)
+                .ignore()
+                .ltrim(history_key, 0, history_size as isize - 1)
+                .ignore();
+        }
+
+        Ok(())
+    }
+
+    fn has_records_after(
+        operations: &[StoreOperation],
+        record_name: &RecordName,
+        record_types: &[String],
+    ) -> bool {
+        // A name keeps records if any of its types is set, or if any of its current types is \
+        //   not removed by operations
+        operations.iter().any(|operation| match operation {
+            StoreOperation::Set(record) => &record.name == record_name,
+            _ => false,
+        }) || record_types.iter().any(|record_type| {
+            !operations.iter().any(|operation| match operation {
+                StoreOperation::Remove(other_name, other_type) => {
+                    other_name == record_name && other_type.to_str() == record_type
+                }
+                _ => false,
+            })
+        })
+    }
+
+    fn pop_cache(zone_name: &ZoneName, record_name: &RecordName, store_key: &str) {
+        STORE_CACHE.pop(store_key);
+        STORE_CACHE.pop(&StoreKey::to_types_key(zone_name, record_name));
+
+        // Names above this name may have gained or lost a descendant
+        for descendants_key in StoreKey::to_ancestors_descendants_keys(zone_name, record_name) {
+            STORE_CACHE.pop(&descendants_key);
+        }
+    }
+
+    fn limits_acquire(&self) -> Result<Instant, ()> {
+        // First, check if limit counters need to be reset and acquire time spent
+        let (time_spent_current_timespan, start_instant) = {
+            let mut limits_rate_write = self.limits.rate.write().unwrap();
+            let now_instant = Instant::now();
+
+            // Counters need to be reset?
+            if now_instant.duration_since(limits_rate_write.time_last)
+                >= LIMITS_GET_REMOTE_TIMESPAN_TOTAL
+            {
+                limits_rate_write.time_last = now_instant;
+                limits_rate_write.time_spent = Duration::new(0, 0);
+
+                debug!(
+                    "started a new time spent chunk in remote store from external ({:?} chunks)",
+                    LIMITS_GET_REMOTE_TIMESPAN_TOTAL
+                );
+            }
+
+            (limits_rate_write.time_spent, now_instant)
+        };
+
+        // Time spent in current timespan is already too great? Reject DNS query.
+        if time_spent_current_timespan >= LIMITS_GET_REMOTE_ALLOWANCE_THRESHOLD {
+            Err(())
+        } else {
+            Ok(start_instant)
+        }
+    }
+
+    fn limits_release(&self, start_instant: Instant) {
+        // Update time spent in current timespan
+        let mut limits_rate_write = self.limits.rate.write().unwrap();
+
+        limits_rate_write.time_spent += start_instant.elapsed();
+
+        debug!(
+            "updated time spent in remote store from external to: {:?} in current chunk",
+            limits_rate_write.time_spent
+        );
+    }
 }
 
 impl Default for StoreLimitsRate {
@@ -558,3 +1548,100 @@ impl StoreRecord {
         }
     }
 }
+
+impl StoreCondition {
+    pub fn is_set(&self) -> bool {
+        self.if_match.is_some() || self.if_none_match.is_some()
+    }
+
+    pub fn check(&self, revision: Option<u64>) -> bool {
+        // Notice: a 'None' revision means that the record does not exist.
+        let is_match = match self.if_match {
+            Some(StoreConditionRevisions::Any) => revision.is_some(),
+            Some(StoreConditionRevisions::List(ref revisions)) => {
+                revision.map(|revision| revisions.contains(&revision)) == Some(true)
+            }
+            None => true,
+        };
+        let is_none_match = match self.if_none_match {
+            Some(StoreConditionRevisions::Any) => revision.is_none(),
+            Some(StoreConditionRevisions::List(ref revisions)) => {
+                revision.map(|revision| !revisions.contains(&revision)) != Some(false)
+            }
+            None => true,
+        };
+
+        is_match && is_none_match
+    }
+}
+
+impl StoreOperation {
+    pub fn key(&self) -> (&RecordName, &RecordType) {
+        match self {
+            StoreOperation::Set(record) => (&record.name, &record.kind),
+            StoreOperation::Remove(record_name, record_type) => (record_name, record_type),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn condition(
+        if_match: Option<StoreConditionRevisions>,
+        if_none_match: Option<StoreConditionRevisions>,
+    ) -> StoreCondition {
+        StoreCondition {
+            if_match,
+            if_none_match,
+        }
+    }
+
+    #[test]
+    fn it_checks_unset_condition() {
+        let condition = StoreCondition::default();
+
+        assert!(!condition.is_set());
+        assert!(condition.check(None));
+        assert!(condition.check(Some(3)));
+    }
+
+    #[test]
+    fn it_checks_if_match_condition() {
+        let any = condition(Some(StoreConditionRevisions::Any), None);
+        let list = condition(Some(StoreConditionRevisions::List(vec![2, 3])), None);
+
+        assert!(any.is_set());
+        assert!(any.check(Some(1)));
+        assert!(!any.check(None));
+        assert!(list.check(Some(3)));
+        assert!(!list.check(Some(4)));
+        assert!(!list.check(None));
+    }
+
+    #[test]
+    fn it_checks_if_none_match_condition() {
+        let any = condition(None, Some(StoreConditionRevisions::Any));
+        let list = condition(None, Some(StoreConditionRevisions::List(vec![2, 3])));
+
+        assert!(any.is_set());
+        assert!(any.check(None));
+        assert!(!any.check(Some(1)));
+        assert!(list.check(Some(4)));
+        assert!(list.check(None));
+        assert!(!list.check(Some(3)));
+    }
+
+    #[test]
+    fn it_checks_combined_condition() {
+        let combined = condition(
+            Some(StoreConditionRevisions::Any),
+            Some(StoreConditionRevisions::List(vec![3])),
+        );
+
+        assert!(combined.check(Some(4)));
+        assert!(!combined.check(Some(3)));
+        assert!(!combined.check(None));
+    }
+}